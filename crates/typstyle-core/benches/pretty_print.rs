@@ -2,10 +2,14 @@ use std::fs;
 
 use criterion::{Criterion, criterion_group, criterion_main};
 use typst_syntax::Source;
-use typstyle_core::{Config, Typstyle};
+use typstyle_core::{AttrStore, Config, Typstyle};
 
 fn bench_pretty(c: &mut Criterion, id: &str, path: &str, config: Config) {
     let content = fs::read_to_string(path).unwrap();
+    bench_pretty_text(c, id, content, config);
+}
+
+fn bench_pretty_text(c: &mut Criterion, id: &str, content: String, config: Config) {
     let source = Source::detached(content);
     let t = Typstyle::new(config);
 
@@ -51,9 +55,67 @@ fn benchmark_pretty(c: &mut Criterion) {
     }
 }
 
+/// (name, content, width) of large equations, generated to scale beyond the fixtures.
+fn large_equations() -> Vec<(&'static str, String, usize)> {
+    let cases = format!(
+        "$ f(x) = cases(\n{}) $\n",
+        (0..500)
+            .map(|i| format!("  x^{i} + {i} & \"if\" x < {i},\n"))
+            .collect::<String>()
+    );
+    let derivation = format!(
+        "$ f(x) &= x \\\n{}  &= 0 $\n",
+        (0..500)
+            .map(|i| format!("  &= f_{i}(x) + sum_(k = 0)^{i} a_k x^k \\\n"))
+            .collect::<String>()
+    );
+    // Derivations with cases nested in their cells. The outer grids are too wide to be
+    // aligned, so their cells are converted again without alignment.
+    let nested = format!(
+        "$ {} $\n",
+        (0..10).fold("a & b".to_string(), |inner, i| {
+            format!("a_{i} &= cases(\n  {inner} & \"if\" x,\n  y & \"else\",\n) \\\n  &= b_{i}")
+        })
+    );
+    vec![
+        ("math-large-cases", cases, 80),
+        ("math-long-derivation", derivation, 80),
+        ("math-nested-aligned", nested, 20),
+    ]
+}
+
+fn benchmark_math_align(c: &mut Criterion) {
+    for (name, content, width) in large_equations() {
+        bench_pretty_text(
+            c,
+            &format!("pretty-{name}"),
+            content,
+            Config::new().with_width(width),
+        );
+    }
+}
+
+/// Measures computing the attributes of the packages, which is done before formatting.
+fn benchmark_attr_store(c: &mut Criterion) {
+    let mut paths = fs::read_dir("../../tests/fixtures/packages")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "typ"))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    let mut group = c.benchmark_group("attrs");
+    for path in paths {
+        let source = Source::detached(fs::read_to_string(&path).unwrap());
+        let name = path.file_stem().unwrap().to_string_lossy();
+        group.bench_function(name.as_ref(), |b| b.iter(|| AttrStore::new(source.root())));
+    }
+    group.finish();
+}
+
 criterion_group! {
     name = benches;
     config = Criterion::default();
-    targets = benchmark_pretty
+    targets = benchmark_pretty, benchmark_math_align, benchmark_attr_store
 }
 criterion_main!(benches);
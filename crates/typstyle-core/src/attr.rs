@@ -1,193 +1,398 @@
-use rustc_hash::FxHashMap;
-use typst_syntax::{Span, SyntaxKind, SyntaxNode, ast};
+use typst_syntax::{SyntaxKind, SyntaxNode, ast};
 
 use crate::ext::StrExt;
 
-#[derive(Debug, Clone, Default)]
-pub struct Attributes {
+/// Formatting attributes of a syntax node, packed into bits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Attributes(u8);
+
+impl Attributes {
     /// Indicates whether formatting is explicitly disabled (`@typstyle off`) or always ignored.
-    pub(self) is_format_disabled: bool,
+    const FORMAT_DISABLED: u8 = 1 << 0;
 
     /// Indicates whether any child node contains a comment.
-    pub(self) has_comment: bool,
+    const HAS_COMMENT: u8 = 1 << 1;
 
     /// Indicates whether any descendant has a multiline string or raw.
-    pub(self) has_multiline_str: bool,
+    const HAS_MULTILINE_STR: u8 = 1 << 2;
 
     /// Indicates whether any descendant has a `MathAlignPoint`.
-    pub(self) has_math_align_point: bool,
+    const HAS_MATH_ALIGN_POINT: u8 = 1 << 3;
 
     /// Indicates whether the node text contains a linebreak.
     /// Currently, it is only used for equations.
-    pub(self) is_multiline: bool,
+    const MULTILINE: u8 = 1 << 4;
+
+    fn has(self, flag: u8) -> bool {
+        self.0 & flag != 0
+    }
+
+    fn set(&mut self, flag: u8, value: bool) {
+        if value {
+            self.0 |= flag;
+        }
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+
+    fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+/// Formatting attributes of a whole document, which may change the layout of any node.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct DocumentAttrs {
+    /// Whether the document may change how enums are numbered.
+    has_enum_numbering: bool,
 }
 
 /// A storage structure that manages formatting attributes for syntax nodes.
+///
+/// Spans are numbered in preorder, so the attributes are stored in a vector sorted by span
+/// and found by binary search. Only nodes with any attributes are stored.
 #[derive(Debug, Default)]
 pub struct AttrStore {
-    /// A mapping between syntax node spans and their associated attributes.
-    attr_map: FxHashMap<Span, Attributes>,
+    /// The raw spans of nodes and their attributes, sorted by span.
+    entries: Vec<(u64, Attributes)>,
+    document: DocumentAttrs,
 }
 
 impl AttrStore {
     /// Creates a new `AttrStore` by computing formatting-related attributes
     /// for all descendants of the given syntax node.
+    ///
+    /// If the node is erroneous, formatting is disabled for its erroneous children and their
+    /// neighbors, also in closed content blocks, so that only the parts without errors
+    /// can be formatted.
     pub fn new(node: &SyntaxNode) -> AttrStore {
+        let mut store = AttrStore::default();
+        store.compute(node);
         if node.erroneous() {
-            return Default::default(); // No attributes for erroneous nodes
+            store.compute_erroneous(node);
         }
-        let mut store = AttrStore::default();
-        store.compute_no_format(node);
-        store.compute_multiline(node);
-        store.compute_math_align_point(node);
+        store.entries.retain(|(_, attrs)| !attrs.is_empty());
         store
     }
 
     /// Checks if a given syntax node contains a comment.
     pub fn has_comment(&self, node: &SyntaxNode) -> bool {
-        self.check_node_attr(node, |attr| attr.has_comment)
+        self.attrs_of(node).has(Attributes::HAS_COMMENT)
     }
 
     pub fn has_multiline_str(&self, node: &SyntaxNode) -> bool {
-        self.check_node_attr(node, |attr| attr.has_multiline_str)
+        self.attrs_of(node).has(Attributes::HAS_MULTILINE_STR)
     }
 
     pub fn has_math_align_point(&self, node: &SyntaxNode) -> bool {
-        self.check_node_attr(node, |attr| attr.has_math_align_point)
+        self.attrs_of(node).has(Attributes::HAS_MATH_ALIGN_POINT)
     }
 
     pub fn can_align_in_math(&self, node: &SyntaxNode) -> bool {
-        self.check_node_attr(node, |attr| {
-            attr.has_math_align_point && !attr.has_multiline_str
-        })
+        let attrs = self.attrs_of(node);
+        attrs.has(Attributes::HAS_MATH_ALIGN_POINT) && !attrs.has(Attributes::HAS_MULTILINE_STR)
     }
 
     /// Checks if a given syntax node or any of its descendants contains a linebreak.
     pub fn is_multiline(&self, node: &SyntaxNode) -> bool {
-        self.check_node_attr(node, |attr| attr.is_multiline)
+        self.attrs_of(node).has(Attributes::MULTILINE)
+    }
+
+    /// Checks if the document has an `enum` set rule, or an `enum` call with `start` or
+    /// `reversed`, so that explicit numbers and `+` markers may render differently.
+    pub fn has_enum_numbering(&self) -> bool {
+        self.document.has_enum_numbering
+    }
+
+    /// Returns the attributes of the whole document.
+    pub(crate) fn document(&self) -> DocumentAttrs {
+        self.document
     }
 
     /// Checks if formatting is explicitly disabled for a given syntax node.
     pub fn is_format_disabled(&self, node: &SyntaxNode) -> bool {
-        self.check_node_attr(node, |attr| attr.is_format_disabled)
+        self.attrs_of(node).has(Attributes::FORMAT_DISABLED)
     }
 
-    fn check_node_attr(&self, node: &SyntaxNode, pred: impl FnOnce(&Attributes) -> bool) -> bool {
-        self.attr_map.get(&node.span()).is_some_and(pred)
+    fn attrs_of(&self, node: &SyntaxNode) -> Attributes {
+        self.entries
+            .binary_search_by_key(&raw_span(node), |&(span, _)| span)
+            .map_or_else(|_| Attributes::default(), |i| self.entries[i].1)
     }
 }
 
+/// How a node is visited, as decided by its parent.
+#[derive(Debug, Clone, Copy, Default)]
+struct Visit {
+    /// Whether to look for comments among the children, including `@typstyle off`.
+    scan_comments: bool,
+    /// Whether formatting is disabled for the node.
+    is_format_disabled: bool,
+    /// Whether formatting is disabled for the first child other than spaces and hashes.
+    disable_first_child: bool,
+}
+
+/// A node being visited, with what is known of its children so far.
+struct Frame<'n> {
+    node: &'n SyntaxNode,
+    /// The children not visited yet.
+    children: std::slice::Iter<'n, SyntaxNode>,
+    /// The index of the entry of the node.
+    index: usize,
+    visit: Visit,
+    is_multiline: bool,
+    has_multiline_str: bool,
+    has_math_align_point: bool,
+    commented: bool,
+    disable_next: bool,
+    disable_first_child: bool,
+}
+
+/// Attributes of a subtree that its parent depends on.
+#[derive(Debug, Clone, Copy, Default)]
+struct Subtree {
+    is_multiline: bool,
+    has_multiline_str: bool,
+    has_math_align_point: bool,
+}
+
 impl AttrStore {
-    fn compute_multiline(&mut self, root: &SyntaxNode) {
-        self.compute_multiline_impl(root);
+    /// Computes the attributes of the node and all its descendants in a single pass.
+    fn compute(&mut self, root: &SyntaxNode) {
+        let visit = Visit {
+            scan_comments: true,
+            ..Default::default()
+        };
+        self.compute_impl(root, visit);
+        // Spans of numbered trees increase in preorder, while detached ones are all the same.
+        if !self.entries.is_sorted_by(|a, b| a.0 < b.0) {
+            self.entries.sort_by_key(|&(span, _)| span);
+            self.entries.dedup_by(|next, prev| {
+                let is_same = next.0 == prev.0;
+                if is_same {
+                    prev.1.merge(next.1);
+                }
+                is_same
+            });
+        }
     }
 
-    fn compute_multiline_impl(&mut self, node: &SyntaxNode) -> (bool, bool) {
-        let mut is_multiline = false;
-        let mut has_multiline_str = false;
-        for child in node.children() {
-            match child.kind() {
-                SyntaxKind::Space => {
-                    if child.text().has_linebreak() {
-                        is_multiline = true;
-                    }
+    fn compute_impl(&mut self, root: &SyntaxNode, visit: Visit) {
+        // An explicit stack is used, as documents may be nested arbitrarily deep.
+        let mut stack = vec![self.enter(root, visit)];
+        while let Some(frame) = stack.last_mut() {
+            let Some(child) = frame.children.next() else {
+                let frame = stack.pop().expect("frame");
+                let res = self.leave(frame);
+                if let Some(parent) = stack.last_mut() {
+                    parent.is_multiline |= res.is_multiline;
+                    parent.has_multiline_str |= res.has_multiline_str;
+                    parent.has_math_align_point |= res.has_math_align_point;
                 }
-                SyntaxKind::BlockComment => {
-                    is_multiline |= child.text().has_linebreak();
+                continue;
+            };
+
+            match child.kind() {
+                SyntaxKind::Space | SyntaxKind::BlockComment => {
+                    frame.is_multiline |= child.text().has_linebreak();
                 }
                 SyntaxKind::Str => {
-                    has_multiline_str |= child.text().has_linebreak();
+                    frame.has_multiline_str |= child.text().has_linebreak();
                 }
                 SyntaxKind::Raw => {
                     let raw = child.cast::<ast::Raw>().expect("raw");
-                    has_multiline_str |= !raw.block() && raw.lines().nth(1).is_some();
+                    frame.has_multiline_str |= !raw.block() && raw.lines().nth(1).is_some();
                 }
                 _ => {}
             }
-            let res = self.compute_multiline_impl(child);
-            is_multiline |= res.0;
-            has_multiline_str |= res.1;
-        }
-        if is_multiline {
-            self.attrs_mut_of(node).is_multiline = true;
+
+            let mut child_visit = Visit::default();
+            if frame.disable_first_child
+                && !matches!(child.kind(), SyntaxKind::Space | SyntaxKind::Hash)
+            {
+                child_visit.is_format_disabled = true;
+                frame.disable_first_child = false;
+            }
+            if frame.visit.scan_comments {
+                match child.kind() {
+                    SyntaxKind::LineComment | SyntaxKind::BlockComment => {
+                        frame.commented = true;
+                        // @typstyle off affects the whole next block
+                        frame.disable_next = child.text().contains("@typstyle off");
+                    }
+                    SyntaxKind::Space | SyntaxKind::Hash => {}
+                    SyntaxKind::Code | SyntaxKind::Math if frame.disable_next => {
+                        // no format nodes with @typstyle off
+                        child_visit.disable_first_child = true;
+                        frame.disable_next = false;
+                    }
+                    kind if frame.disable_next => {
+                        // no format nodes with @typstyle off
+                        child_visit.is_format_disabled |= !kind.is_trivia();
+                        frame.disable_next = false;
+                    }
+                    kind => child_visit.scan_comments = !kind.is_trivia(),
+                }
+            }
+
+            let frame = self.enter(child, child_visit);
+            stack.push(frame);
         }
-        if has_multiline_str {
-            self.attrs_mut_of(node).has_multiline_str = true;
+    }
+
+    /// Starts visiting the node, before its children.
+    fn enter<'n>(&mut self, node: &'n SyntaxNode, visit: Visit) -> Frame<'n> {
+        // Reserve the entry before the children, to keep the entries sorted by span.
+        let index = self.entries.len();
+        self.entries.push((raw_span(node), Attributes::default()));
+        Frame {
+            node,
+            children: node.children(),
+            index,
+            disable_first_child: visit.disable_first_child,
+            visit,
+            is_multiline: false,
+            has_multiline_str: false,
+            has_math_align_point: false,
+            commented: false,
+            disable_next: false,
         }
-        (is_multiline, has_multiline_str)
     }
 
-    fn compute_no_format(&mut self, root: &SyntaxNode) {
-        self.compute_no_format_impl(root);
+    /// Finishes visiting the node, after its children.
+    fn leave(&mut self, frame: Frame) -> Subtree {
+        let node = frame.node;
+        self.document.has_enum_numbering |= sets_enum_numbering(node);
+
+        // Align points only count through nested math.
+        let kind = node.kind();
+        let has_math_align_point = frame.has_math_align_point
+            && matches!(kind, SyntaxKind::Math | SyntaxKind::MathDelimited);
+
+        let attrs = &mut self.entries[frame.index].1;
+        attrs.set(Attributes::FORMAT_DISABLED, frame.visit.is_format_disabled);
+        attrs.set(Attributes::HAS_COMMENT, frame.commented);
+        attrs.set(Attributes::MULTILINE, frame.is_multiline);
+        attrs.set(Attributes::HAS_MULTILINE_STR, frame.has_multiline_str);
+        attrs.set(Attributes::HAS_MATH_ALIGN_POINT, has_math_align_point);
+        Subtree {
+            is_multiline: frame.is_multiline,
+            has_multiline_str: frame.has_multiline_str,
+            has_math_align_point: has_math_align_point || kind == SyntaxKind::MathAlignPoint,
+        }
     }
 
-    fn compute_no_format_impl(&mut self, node: &SyntaxNode) {
-        let mut disable_next = false;
-        let mut commented = false;
-        for child in node.children() {
-            match child.kind() {
-                SyntaxKind::LineComment | SyntaxKind::BlockComment => {
-                    commented = true;
-                    // @typstyle off affects the whole next block
-                    disable_next = child.text().contains("@typstyle off");
+    fn compute_erroneous(&mut self, node: &SyntaxNode) {
+        // Markup nested in closed content blocks is handled like the top level.
+        let mut markups = vec![node];
+        while let Some(markup) = markups.pop() {
+            // Skip trivia and hashes, which are kept as is.
+            let significant = markup
+                .children()
+                .filter(|child| !child.kind().is_trivia() && child.kind() != SyntaxKind::Hash)
+                .collect::<Vec<_>>();
+            for (i, child) in significant.iter().enumerate() {
+                if !child.erroneous() {
+                    continue;
                 }
-                SyntaxKind::Space | SyntaxKind::Hash => {}
-                SyntaxKind::Code | SyntaxKind::Math if disable_next => {
-                    // no format nodes with @typstyle off
-                    self.disable_first_nontrivial_child(child);
-                    disable_next = false;
+                if let Some(inner) = closed_content_block_body(child) {
+                    markups.push(inner);
+                    continue;
                 }
-                _ if disable_next => {
-                    // no format nodes with @typstyle off
-                    if !child.kind().is_trivia() {
-                        self.attrs_mut_of(child).is_format_disabled = true;
-                    }
-                    disable_next = false;
-                }
-                _ => {
-                    if !child.kind().is_trivia() {
-                        self.compute_no_format_impl(child);
-                    }
+                // Errors may affect how the neighbors are parsed, so they are kept as well.
+                for neighbor in &significant[i.saturating_sub(1)..(i + 2).min(significant.len())] {
+                    self.attrs_mut_of(neighbor)
+                        .set(Attributes::FORMAT_DISABLED, true);
                 }
             }
         }
-        if commented {
-            self.attrs_mut_of(node).has_comment = true;
-        }
     }
 
-    fn disable_first_nontrivial_child(&mut self, node: &SyntaxNode) {
-        node.children()
-            .find(|it| !matches!(it.kind(), SyntaxKind::Space | SyntaxKind::Hash))
-            .inspect(|it| self.attrs_mut_of(it).is_format_disabled = true);
-    }
-
-    fn compute_math_align_point(&mut self, root: &SyntaxNode) {
-        self.compute_math_align_point_impl(root);
+    fn attrs_mut_of(&mut self, node: &SyntaxNode) -> &mut Attributes {
+        let span = raw_span(node);
+        let index = match self.entries.binary_search_by_key(&span, |&(span, _)| span) {
+            Ok(index) => index,
+            Err(index) => {
+                self.entries.insert(index, (span, Attributes::default()));
+                index
+            }
+        };
+        &mut self.entries[index].1
     }
+}
 
-    fn compute_math_align_point_impl(&mut self, node: &SyntaxNode) -> bool {
-        let node_kind = node.kind();
-        if node_kind == SyntaxKind::MathAlignPoint {
-            return true;
-        }
-        if node_kind.is_trivia() {
-            return false;
-        }
-        let mut has_math_align_point = false;
-        for child in node.children() {
-            has_math_align_point |= self.compute_math_align_point_impl(child);
-        }
-        if has_math_align_point && matches!(node_kind, SyntaxKind::Math | SyntaxKind::MathDelimited)
+/// Returns the markup in a content block, if its brackets are closed,
+/// so that errors in it do not affect the parsing outside.
+fn closed_content_block_body(node: &SyntaxNode) -> Option<&SyntaxNode> {
+    match node.children().as_slice() {
+        [open, body, close]
+            if node.kind() == SyntaxKind::ContentBlock
+                && open.kind() == SyntaxKind::LeftBracket
+                && body.kind() == SyntaxKind::Markup
+                && close.kind() == SyntaxKind::RightBracket =>
         {
-            self.attrs_mut_of(node).has_math_align_point = true;
-            true
-        } else {
-            false
+            Some(body)
         }
+        _ => None,
     }
+}
 
-    fn attrs_mut_of(&mut self, node: &SyntaxNode) -> &mut Attributes {
-        self.attr_map.entry(node.span()).or_default()
+/// Whether the node is an `enum` set rule, or an `enum` call with `start` or `reversed`.
+fn sets_enum_numbering(node: &SyntaxNode) -> bool {
+    let is_enum =
+        |expr: ast::Expr| matches!(expr, ast::Expr::Ident(ident) if ident.as_str() == "enum");
+    if let Some(set_rule) = node.cast::<ast::SetRule>() {
+        return is_enum(set_rule.target());
+    }
+    if let Some(func_call) = node.cast::<ast::FuncCall>() {
+        return is_enum(func_call.callee())
+            && func_call.args().items().any(|arg| match arg {
+                ast::Arg::Named(named) => matches!(named.name().as_str(), "start" | "reversed"),
+                _ => false,
+            });
+    }
+    false
+}
+
+fn raw_span(node: &SyntaxNode) -> u64 {
+    node.span().into_raw().get()
+}
+
+#[cfg(test)]
+mod tests {
+    use typst_syntax::Source;
+
+    use super::*;
+
+    #[test]
+    fn test_attrs() {
+        let source = Source::detached("// @typstyle off\n#f( a )\n#g( b )\n$ a &= b\n  &= c $\n");
+        let root = source.root();
+        let attrs = AttrStore::new(root);
+        let children = |node: &SyntaxNode, kind| {
+            node.children()
+                .filter(|child| child.kind() == kind)
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        assert!(attrs.has_comment(root));
+        let [f, g] = children(root, SyntaxKind::FuncCall).try_into().unwrap();
+        assert!(attrs.is_format_disabled(&f));
+        assert!(!attrs.is_format_disabled(&g));
+        assert!(!attrs.has_comment(&g));
+
+        let [equation] = children(root, SyntaxKind::Equation).try_into().unwrap();
+        let [math] = children(&equation, SyntaxKind::Math).try_into().unwrap();
+        assert!(attrs.is_multiline(&equation));
+        assert!(attrs.can_align_in_math(&math));
+        assert!(!attrs.has_math_align_point(&equation));
+
+        // Detached nodes share the same span, so their attributes are merged.
+        let detached = typst_syntax::parse("// @typstyle off\n#f( a )");
+        let attrs = AttrStore::new(&detached);
+        assert!(attrs.has_comment(&detached));
     }
 }
@@ -0,0 +1,57 @@
+//! Limits on the input size and time of formatting.
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{Duration, Instant};
+
+use crate::Config;
+
+/// A limit that was exceeded, so formatting was skipped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Budget {
+    /// The input is larger than `max_input_size`.
+    InputSize,
+    /// Formatting took longer than `time_limit_ms`.
+    Time,
+}
+
+impl std::fmt::Display for Budget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::InputSize => "input size",
+            Self::Time => "time",
+        })
+    }
+}
+
+/// The time when formatting should stop.
+///
+/// Time is not available in WebAssembly, so the deadline never passes there.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Deadline {
+    #[cfg(not(target_arch = "wasm32"))]
+    instant: Option<Instant>,
+}
+
+impl Deadline {
+    /// Starts the clock for `time_limit_ms`.
+    #[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+    pub fn start(config: &Config) -> Self {
+        Self {
+            #[cfg(not(target_arch = "wasm32"))]
+            instant: (config.time_limit_ms > 0)
+                .then(|| Instant::now() + Duration::from_millis(config.time_limit_ms)),
+        }
+    }
+
+    pub fn has_passed(&self) -> bool {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.instant
+                .is_some_and(|instant| Instant::now() >= instant)
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            false
+        }
+    }
+}
@@ -4,15 +4,55 @@
 #[cfg_attr(feature = "serde", serde(default))]
 pub struct Config {
     /// Number of spaces to use for each indentation level.
+    /// With tab indentation, it is the number of columns a tab takes.
     pub tab_spaces: usize,
+    /// Whether to indent with spaces or tabs.
+    pub indent_style: IndentStyle,
     /// Maximum width of each line.
     pub max_width: usize,
     /// Maximum number of consecutive blank lines allowed.
     pub blank_lines_upper_bound: usize,
+    /// Minimum number of blank lines around headings, block equations, block raws
+    /// and function definitions in top-level markup. `0` keeps existing lines as is.
+    ///
+    /// Blank lines are only added where they do not change the output.
+    pub blank_lines_around_blocks: usize,
     /// When `true`, consecutive whitespace in markup is collapsed into a single space.
     pub collapse_markup_spaces: bool,
     /// When `true`, import items are sorted alphabetically.
     pub reorder_import_items: bool,
+    /// The order used to sort import items when `reorder_import_items` is enabled.
+    pub import_sort_order: ImportSortOrder,
+    /// When to add trailing commas to arguments, arrays, dictionaries, parameters and destructurings.
+    pub trailing_comma: TrailingComma,
+    /// The line ending style of the output.
+    /// All line endings are converted, including those in multiline strings and raw blocks.
+    pub newline_style: NewlineStyle,
+    /// When `true`, a leading byte order mark is removed. Otherwise, it is kept.
+    pub strip_bom: bool,
+    /// How to write the markers of enum items numbered sequentially from 1.
+    pub enum_marker_style: EnumMarkerStyle,
+    /// When `true`, descriptions of consecutive term items are aligned in one column.
+    pub align_term_descriptions: bool,
+    /// When `true`, paragraphs in consecutive line comments are reflowed to fit within `max_width`.
+    pub wrap_comments: bool,
+    /// When `true`, Typst code fenced with `example` or `typ` in doc comments is formatted.
+    pub format_code_in_doc_comments: bool,
+    /// When `true`, raw blocks tagged `typ`, `typc` or `typm` are formatted as markup, code or math.
+    pub format_code_in_raw_blocks: bool,
+    /// When `true`, markup with syntax errors is formatted on a best-effort basis.
+    /// Top-level nodes with errors, and the nodes next to them, are kept as is.
+    pub format_erroneous: bool,
+    /// Maximum depth of nested expressions to format.
+    /// Expressions nested deeper are kept as is.
+    pub max_nesting_depth: usize,
+    /// Maximum size of the input in bytes. Larger inputs are not formatted.
+    /// `0` means no limit.
+    pub max_input_size: usize,
+    /// Maximum time of formatting in milliseconds, checked between nodes.
+    /// When it is exceeded, formatting is aborted. `0` means no limit.
+    /// It has no effect in WebAssembly, where time is not available.
+    pub time_limit_ms: u64,
     /// When `true`, text in markup will be wrapped to fit within `max_width`.
     /// Implies `collapse_markup_spaces`.
     pub wrap_text: bool,
@@ -22,10 +62,25 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             tab_spaces: 2,
+            indent_style: IndentStyle::default(),
             max_width: 80,
             blank_lines_upper_bound: 1,
+            blank_lines_around_blocks: 0,
             reorder_import_items: true,
+            import_sort_order: ImportSortOrder::default(),
             collapse_markup_spaces: false,
+            trailing_comma: TrailingComma::default(),
+            newline_style: NewlineStyle::default(),
+            strip_bom: false,
+            enum_marker_style: EnumMarkerStyle::default(),
+            align_term_descriptions: false,
+            wrap_comments: false,
+            format_code_in_doc_comments: false,
+            format_code_in_raw_blocks: false,
+            format_erroneous: false,
+            max_nesting_depth: 128,
+            max_input_size: 0,
+            time_limit_ms: 0,
             wrap_text: false,
         }
     }
@@ -56,3 +111,88 @@ impl Config {
         self
     }
 }
+
+/// The characters used for indentation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum IndentStyle {
+    /// Indent with `tab_spaces` spaces per level.
+    #[default]
+    Spaces,
+    /// Indent with one tab per level.
+    Tabs,
+}
+
+/// The line ending style.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum NewlineStyle {
+    /// Use the line ending of the first line in the input.
+    #[default]
+    Auto,
+    /// Use `\n`.
+    Lf,
+    /// Use `\r\n`.
+    Crlf,
+    /// Use `\r\n` on Windows and `\n` elsewhere.
+    Native,
+}
+
+impl NewlineStyle {
+    /// Whether lines should end with `\r\n` when formatting the given input.
+    pub fn is_crlf(self, input: &str) -> bool {
+        match self {
+            Self::Auto => input.find('\n').is_some_and(|i| input[..i].ends_with('\r')),
+            Self::Lf => false,
+            Self::Crlf => true,
+            Self::Native => cfg!(windows),
+        }
+    }
+}
+
+/// The order used to sort import items.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum ImportSortOrder {
+    /// Compare the item text byte by byte, so uppercase letters come first.
+    #[default]
+    Bytewise,
+    /// Compare the item text ignoring case.
+    CaseInsensitive,
+    /// Compare runs of digits by their numeric value, so `item2` comes before `item10`.
+    Natural,
+    /// Compare the names bound by the items, i.e., the alias of a renamed item.
+    Alias,
+}
+
+/// When to add trailing commas to lists.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum TrailingComma {
+    /// Add trailing commas when the list spans multiple lines.
+    #[default]
+    Multiline,
+    /// Never add trailing commas, unless required by the syntax, e.g., `(1,)`.
+    Never,
+}
+
+/// How to write enum markers.
+///
+/// Only the markers of an enum numbered sequentially from 1 are changed. Documents with
+/// `enum` set rules, or `enum` calls with `start` or `reversed`, are kept as is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum EnumMarkerStyle {
+    /// Keep markers as written.
+    #[default]
+    Keep,
+    /// Use `+` for all items.
+    Auto,
+    /// Use explicit numbers like `1.` for all items.
+    Numbered,
+}
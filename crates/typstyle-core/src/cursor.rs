@@ -0,0 +1,158 @@
+//! Cursor-preserving formatting.
+//!
+//! Cursors are mapped by aligning the non-whitespace characters of the original and formatted
+//! text, as formatting mostly changes whitespace. A cursor inside or next to an unchanged token
+//! keeps its position relative to that token.
+
+use similar::{Algorithm, DiffTag, capture_diff_slices};
+
+use crate::{Error, Typstyle};
+
+/// Result of formatting with cursors.
+#[derive(Debug, Clone)]
+pub struct CursorResult {
+    /// The formatted text.
+    pub content: String,
+    /// The positions of the cursors in the formatted text, in the order they were given.
+    pub cursors: Vec<CursorPosition>,
+}
+
+/// A position in the formatted text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CursorPosition {
+    /// The UTF-8 byte offset.
+    pub offset: usize,
+    /// The UTF-16 code unit offset, as used in JavaScript strings.
+    pub utf16_offset: usize,
+}
+
+impl Typstyle {
+    /// Formats the text, and maps the cursors to the formatted text.
+    ///
+    /// # Arguments
+    /// - `text`: The text to format.
+    /// - `cursors`: The UTF-8 byte offsets of the cursors in `text`.
+    pub fn format_text_with_cursors(
+        &self,
+        text: &str,
+        cursors: &[usize],
+    ) -> Result<CursorResult, Error> {
+        let content = self.format_text(text).render()?;
+        let cursors = map_cursors(text, &content, cursors)
+            .into_iter()
+            .map(|offset| CursorPosition {
+                offset,
+                utf16_offset: content[..offset].encode_utf16().count(),
+            })
+            .collect();
+        Ok(CursorResult { content, cursors })
+    }
+}
+
+/// Maps byte offsets in `original` to byte offsets in `formatted`.
+pub fn map_cursors(original: &str, formatted: &str, cursors: &[usize]) -> Vec<usize> {
+    let old = non_whitespace_chars(original);
+    let new = non_whitespace_chars(formatted);
+    let old_chars = old.iter().map(|&(_, ch)| ch).collect::<Vec<_>>();
+    let new_chars = new.iter().map(|&(_, ch)| ch).collect::<Vec<_>>();
+
+    // For each old character, the index of the new character taking its place,
+    // and whether it is unchanged.
+    let mut targets = vec![(0, false); old.len()];
+    for op in capture_diff_slices(Algorithm::Myers, &old_chars, &new_chars) {
+        let (tag, old_range, new_range) = op.as_tag_tuple();
+        for (k, i) in old_range.enumerate() {
+            targets[i] = match tag {
+                DiffTag::Equal => (new_range.start + k, true),
+                _ => (new_range.start + k.min(new_range.len()), false),
+            };
+        }
+    }
+
+    let start_of = |j: usize| new.get(j).map_or(formatted.len(), |&(pos, _)| pos);
+    let end_of = |j: usize| new[j].0 + new[j].1.len_utf8();
+    cursors
+        .iter()
+        .map(|&cursor| {
+            let i = old.partition_point(|&(pos, _)| pos < cursor);
+            match (
+                i.checked_sub(1).map(|p| (old[p], targets[p])),
+                targets.get(i),
+            ) {
+                // Right before a character.
+                (_, Some(&(j, _))) if old[i].0 == cursor => start_of(j),
+                // Right after an unchanged character.
+                (Some(((pos, ch), (j, true))), _) if pos + ch.len_utf8() == cursor => end_of(j),
+                // In whitespace, or after a changed character.
+                (_, Some(&(j, _))) => start_of(j),
+                (_, None) => formatted.len(),
+            }
+        })
+        .collect()
+}
+
+/// Collects the byte offsets and non-whitespace characters of the text.
+fn non_whitespace_chars(text: &str) -> Vec<(usize, char)> {
+    text.char_indices()
+        .filter(|(_, ch)| !ch.is_whitespace())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    fn format_with_cursors(text: &str) -> String {
+        let cursors = text.match_indices('|').map(|(i, _)| i).collect::<Vec<_>>();
+        let text = text.replace('|', "");
+        // Offsets after removing the markers.
+        let cursors = cursors
+            .iter()
+            .enumerate()
+            .map(|(k, &i)| i - k)
+            .collect::<Vec<_>>();
+        let res = Typstyle::new(Config::default())
+            .format_text_with_cursors(&text, &cursors)
+            .unwrap();
+        let mut content = res.content;
+        for cursor in res.cursors.iter().rev() {
+            content.insert(cursor.offset, '|');
+        }
+        content
+    }
+
+    #[test]
+    fn test_cursor_in_token() {
+        assert_eq!(
+            format_with_cursors("#let  fo|o  =  (1,2)"),
+            "#let fo|o = (1, 2)\n"
+        );
+        assert_eq!(
+            format_with_cursors("#let foo=(|1,2|)"),
+            "#let foo = (|1, 2|)\n"
+        );
+    }
+
+    #[test]
+    fn test_cursor_in_whitespace() {
+        assert_eq!(format_with_cursors("#let a| =  1"), "#let a| = 1\n");
+        assert_eq!(format_with_cursors("#let a =  |1"), "#let a = |1\n");
+        assert_eq!(format_with_cursors("#let a =  1\n|"), "#let a = 1\n|");
+    }
+
+    #[test]
+    fn test_cursor_multibyte() {
+        let res = Typstyle::new(Config::default())
+            .format_text_with_cursors("#let  你好 = \"😀\"", &["#let  你好 = \"😀".len()])
+            .unwrap();
+        assert_eq!(res.content, "#let 你好 = \"😀\"\n");
+        assert_eq!(
+            res.cursors,
+            [CursorPosition {
+                offset: "#let 你好 = \"😀".len(),
+                utf16_offset: "#let 你好 = \"😀".encode_utf16().count(),
+            }]
+        );
+    }
+}
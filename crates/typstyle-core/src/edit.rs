@@ -0,0 +1,238 @@
+//! Formatting results as minimal text edits.
+//!
+//! Applying edits instead of replacing the whole text lets editors keep folds, markers
+//! and undo history in the unchanged parts.
+
+use std::ops::Range;
+
+use similar::{Algorithm, DiffOp, capture_diff_slices};
+use typst_syntax::Source;
+
+use crate::{Error, Typstyle, partial::RangeResult};
+
+/// A replacement of a range in the original text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    /// The UTF-8 byte range in the original text.
+    pub range: Range<usize>,
+    /// The UTF-16 code unit range in the original text.
+    pub utf16_range: Range<usize>,
+    /// The text to replace the range with.
+    pub replacement: String,
+}
+
+impl Typstyle {
+    /// Formats the source, and returns the changes as minimal text edits against it.
+    ///
+    /// The edits are sorted and do not overlap, so they can all be applied to the original text.
+    pub fn format_source_edits(&self, source: Source) -> Result<Vec<TextEdit>, Error> {
+        let formatted = self.format_source(source.clone()).render()?;
+        Ok(compute_edits(&source, 0..source.text().len(), &formatted))
+    }
+
+    /// Formats the smallest syntax node that fully covers the given byte range, like
+    /// [`Typstyle::format_source_range`], and returns the changes as minimal text edits.
+    pub fn format_source_range_edits(
+        &self,
+        source: Source,
+        utf8_range: Range<usize>,
+    ) -> Result<Vec<TextEdit>, Error> {
+        let res = self.format_source_range(source.clone(), utf8_range)?;
+        Ok(compute_edits(&source, res.source_range, &res.content))
+    }
+
+    /// Formats the nodes covering each of the given byte ranges, and returns the changes as
+    /// minimal text edits.
+    ///
+    /// Each range is widened to its covering nodes, like [`Typstyle::format_source_range`].
+    /// Ranges whose covering nodes overlap are formatted together. The edits are sorted, do not
+    /// overlap, and all refer to the original text.
+    pub fn format_source_ranges(
+        &self,
+        source: Source,
+        utf8_ranges: &[Range<usize>],
+    ) -> Result<Vec<TextEdit>, Error> {
+        let mut ranges = utf8_ranges.to_vec();
+        ranges.sort_by_key(|range| (range.start, range.end));
+
+        let mut results: Vec<RangeResult> = vec![];
+        for range in ranges {
+            let mut res = self.format_source_range(source.clone(), range)?;
+            if res.source_range.is_empty() && res.content.is_empty() {
+                continue; // No node covers the range.
+            }
+            // Widening may make the node overlap the previous ones, so merge them until it does not.
+            while let Some(last) =
+                results.pop_if(|last| last.source_range.end > res.source_range.start)
+            {
+                let start = last.source_range.start.min(res.source_range.start);
+                let end = last.source_range.end.max(res.source_range.end);
+                res = self.format_source_range(source.clone(), start..end)?;
+            }
+            results.push(res);
+        }
+
+        let mut edits: Vec<TextEdit> = vec![];
+        for res in results {
+            for edit in compute_edits(&source, res.source_range, &res.content) {
+                // Edits of touching nodes may be adjacent.
+                match edits.last_mut() {
+                    Some(last) if last.range.end == edit.range.start => {
+                        last.range.end = edit.range.end;
+                        last.utf16_range.end = edit.utf16_range.end;
+                        last.replacement.push_str(&edit.replacement);
+                    }
+                    _ => edits.push(edit),
+                }
+            }
+        }
+        Ok(edits)
+    }
+}
+
+/// Computes minimal edits that replace `range` of the source with `replacement`.
+///
+/// Lines are compared first, and characters are compared within changed lines.
+/// Adjacent edits are merged. The edits are sorted and do not overlap.
+pub fn compute_edits(source: &Source, range: Range<usize>, replacement: &str) -> Vec<TextEdit> {
+    let original = &source.text()[range.clone()];
+    let mut edits: Vec<(Range<usize>, Range<usize>)> = vec![];
+    let mut push = |old: Range<usize>, new: Range<usize>| match edits.last_mut() {
+        Some(last) if last.0.end == old.start => {
+            last.0.end = old.end;
+            last.1.end = new.end;
+        }
+        _ => edits.push((old, new)),
+    };
+
+    let (old_lines, old_bounds) = split_lines(original);
+    let (new_lines, new_bounds) = split_lines(replacement);
+    for op in capture_diff_slices(Algorithm::Myers, &old_lines, &new_lines) {
+        if matches!(op, DiffOp::Equal { .. }) {
+            continue;
+        }
+        let old_range = old_bounds[op.old_range().start]..old_bounds[op.old_range().end];
+        let new_range = new_bounds[op.new_range().start]..new_bounds[op.new_range().end];
+        if old_range.is_empty() || new_range.is_empty() {
+            push(old_range, new_range);
+            continue;
+        }
+
+        let (old_chars, old_char_bounds) = split_chars(&original[old_range.clone()]);
+        let (new_chars, new_char_bounds) = split_chars(&replacement[new_range.clone()]);
+        for op in capture_diff_slices(Algorithm::Myers, &old_chars, &new_chars) {
+            if matches!(op, DiffOp::Equal { .. }) {
+                continue;
+            }
+            push(
+                old_range.start + old_char_bounds[op.old_range().start]
+                    ..old_range.start + old_char_bounds[op.old_range().end],
+                new_range.start + new_char_bounds[op.new_range().start]
+                    ..new_range.start + new_char_bounds[op.new_range().end],
+            );
+        }
+    }
+
+    let to_utf16 = |pos: usize| {
+        source
+            .lines()
+            .byte_to_utf16(pos)
+            .expect("the position should be in the source")
+    };
+    edits
+        .into_iter()
+        .map(|(old, new)| {
+            let old = range.start + old.start..range.start + old.end;
+            TextEdit {
+                utf16_range: to_utf16(old.start)..to_utf16(old.end),
+                range: old,
+                replacement: replacement[new].to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Splits the text into lines with their line endings, and returns the lines and their bounds.
+fn split_lines(text: &str) -> (Vec<&str>, Vec<usize>) {
+    let lines = text.split_inclusive('\n').collect::<Vec<_>>();
+    let bounds = bounds_of(lines.iter().map(|line| line.len()));
+    (lines, bounds)
+}
+
+/// Splits the text into chars, and returns the chars and their bounds.
+fn split_chars(text: &str) -> (Vec<char>, Vec<usize>) {
+    let chars = text.chars().collect::<Vec<_>>();
+    let bounds = bounds_of(chars.iter().map(|ch| ch.len_utf8()));
+    (chars, bounds)
+}
+
+/// Returns the start offsets of consecutive pieces with the given lengths, and the end offset.
+fn bounds_of(lens: impl Iterator<Item = usize>) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(lens.scan(0, |offset, len| {
+            *offset += len;
+            Some(*offset)
+        }))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    fn apply(text: &str, edits: &[TextEdit]) -> String {
+        let mut res = text.to_string();
+        for edit in edits.iter().rev() {
+            res.replace_range(edit.range.clone(), &edit.replacement);
+        }
+        res
+    }
+
+    #[test]
+    fn test_edits_minimal() {
+        let source = Source::detached("#let a=1\n#let b = 2\n");
+        let edits = Typstyle::new(Config::default())
+            .format_source_edits(source)
+            .unwrap();
+        assert_eq!(
+            edits,
+            [
+                TextEdit {
+                    range: 6..6,
+                    utf16_range: 6..6,
+                    replacement: " ".to_string(),
+                },
+                TextEdit {
+                    range: 7..7,
+                    utf16_range: 7..7,
+                    replacement: " ".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_edits_apply() {
+        let text = "= 标题\n#let  f(x,y)=x+y\n\n\n\n#f(1,\n2)  \ntext";
+        let t = Typstyle::new(Config::default());
+        let edits = t.format_source_edits(Source::detached(text)).unwrap();
+        assert!(edits.is_sorted_by(|a, b| a.range.end < b.range.start));
+        assert_eq!(apply(text, &edits), t.format_text(text).render().unwrap());
+        // The first edit removes a space after `#let`, which is after 4 more bytes than UTF-16 units.
+        assert_eq!(edits[0].range.len(), 1);
+        assert_eq!(edits[0].utf16_range.start + 4, edits[0].range.start);
+    }
+
+    #[test]
+    fn test_edits_ranges() {
+        let text = "#(1+1)\n#(2+2)\n#(3+3)\n#(4+4)\n";
+        let t = Typstyle::new(Config::default());
+        // The second and third ranges overlap.
+        let edits = t
+            .format_source_ranges(Source::detached(text), &[23..26, 2..5, 3..4])
+            .unwrap();
+        assert!(edits.is_sorted_by(|a, b| a.range.end < b.range.start));
+        assert_eq!(apply(text, &edits), "#(1 + 1)\n#(2+2)\n#(3+3)\n#(4 + 4)\n");
+    }
+}
@@ -0,0 +1,123 @@
+//! Reports of nodes formatted by fallback paths.
+
+use std::ops::Range;
+
+/// A node formatted by a fallback path, kept as is or laid out more conservatively than usual.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fallback {
+    /// The UTF-8 byte range of the node in the given text.
+    pub range: Range<usize>,
+    /// Why the fallback path was taken.
+    pub reason: FallbackReason,
+}
+
+/// Why a node was formatted by a fallback path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FallbackReason {
+    /// Formatting is disabled by `@typstyle off`, or by syntax errors nearby.
+    FormatDisabled,
+    /// The node is nested deeper than `max_nesting_depth`.
+    MaxNestingDepth,
+    /// A table has block comments in its arguments.
+    TableBlockComment,
+    /// A table has no positional arguments as cells.
+    TableNoCells,
+    /// The number of columns of a table is not a simple constant, or may be given by spread
+    /// arguments.
+    TableUnknownColumns,
+    /// An equation with align points has multiline strings or raws.
+    MathAlignMultilineStr,
+    /// An equation with align points would exceed the max width if aligned.
+    MathAlignTooWide,
+    /// A chain of method calls is on a line with text, so it cannot be broken.
+    DotChainInline,
+}
+
+impl FallbackReason {
+    /// Returns a short code for the reason, like `format-disabled`.
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::FormatDisabled => "format-disabled",
+            Self::MaxNestingDepth => "max-nesting-depth",
+            Self::TableBlockComment => "table-block-comment",
+            Self::TableNoCells => "table-no-cells",
+            Self::TableUnknownColumns => "table-unknown-columns",
+            Self::MathAlignMultilineStr => "math-align-multiline-str",
+            Self::MathAlignTooWide => "math-align-too-wide",
+            Self::DotChainInline => "dot-chain-inline",
+        }
+    }
+}
+
+impl std::fmt::Display for FallbackReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::FormatDisabled => {
+                "formatting is disabled by `@typstyle off` or syntax errors nearby, so it is kept as is"
+            }
+            Self::MaxNestingDepth => {
+                "it is nested deeper than `max_nesting_depth`, so it is kept as is"
+            }
+            Self::TableBlockComment => {
+                "the table has block comments, so it is not laid out in columns"
+            }
+            Self::TableNoCells => "the table has no cells, so it is not laid out in columns",
+            Self::TableUnknownColumns => {
+                "the number of columns is unknown, so the table is not laid out in columns"
+            }
+            Self::MathAlignMultilineStr => {
+                "the equation has multiline strings, so it is not aligned"
+            }
+            Self::MathAlignTooWide => "the aligned equation would be too wide, so it is not aligned",
+            Self::DotChainInline => {
+                "the chain is on a line with text, so it is kept on one line"
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Typstyle;
+
+    #[test]
+    fn test_explain() {
+        let t = Typstyle::default();
+        let text = "\u{feff}// @typstyle off\n#f( a )\n#table(columns: x, [a])\n#g( b )\n";
+        let fallbacks = t.format_text(text).explain().unwrap();
+        assert_eq!(
+            fallbacks,
+            [
+                Fallback {
+                    range: 21..27,
+                    reason: FallbackReason::FormatDisabled,
+                },
+                Fallback {
+                    range: 29..51,
+                    reason: FallbackReason::TableUnknownColumns,
+                },
+            ]
+        );
+        assert_eq!(&text[21..27], "f( a )");
+        assert_eq!(&text[29..51], "table(columns: x, [a])");
+    }
+
+    #[test]
+    fn test_explain_after_render() {
+        let t = Typstyle::new(crate::Config::new().with_width(20));
+        let text = "$ a &= b + c + d + e + f + g \\ &= h $\n";
+        let f = t.format_text(text);
+        let before = f.explain().unwrap();
+        f.render().unwrap();
+        assert_eq!(f.explain().unwrap(), before);
+        assert_eq!(
+            before,
+            [Fallback {
+                range: 2..35,
+                reason: FallbackReason::MathAlignTooWide,
+            }]
+        );
+        assert_eq!(&text[2..35], "a &= b + c + d + e + f + g \\ &= h");
+    }
+}
@@ -0,0 +1,252 @@
+//! Formatting documents again after edits, reusing the output of unchanged items.
+//!
+//! A top-level item on lines of its own, like `#let f(x) = x` or a heading, starts at the
+//! beginning of a line and is followed by a linebreak. Its layout does not depend on the rest
+//! of the document, so its formatted text can be kept and spliced into later outputs, as long
+//! as its subtree is unchanged and the attributes of the whole document, like whether it sets
+//! how enums are numbered, are the same.
+
+use std::ops::Range;
+
+use rustc_hash::FxHashMap;
+use typst_syntax::{Source, Span, SyntaxNode};
+
+use crate::{Config, Error, Typstyle, attr::DocumentAttrs};
+
+/// The formatted text of the top-level items of a document, kept to format it again after edits.
+#[derive(Debug, Clone, Default)]
+pub struct FormatCache {
+    /// The configuration the items were formatted with.
+    config: Option<Config>,
+    /// The attributes of the document the items were formatted in.
+    document: DocumentAttrs,
+    items: FxHashMap<Span, CachedItem>,
+    /// The number of items reused by the last successful format.
+    reused: usize,
+}
+
+impl FormatCache {
+    /// Returns the number of items kept.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns whether no items are kept.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns the number of items reused, instead of formatted again, by the last
+    /// successful format.
+    pub fn reused(&self) -> usize {
+        self.reused
+    }
+}
+
+/// The formatted text of a top-level item, without the leading `#`.
+#[derive(Debug, Clone)]
+pub(crate) struct CachedItem {
+    node: SyntaxNode,
+    text: String,
+    /// The lines of the text starting with line comments, after indentation.
+    comment_lines: Vec<usize>,
+}
+
+/// The items of the previous format, and those of the current one.
+#[derive(Debug, Default)]
+pub(crate) struct ItemCache {
+    pub previous: FxHashMap<Span, CachedItem>,
+    pub next: FxHashMap<Span, CachedItem>,
+    /// The spans of the items taken from the previous format.
+    pub reused: Vec<Span>,
+}
+
+impl ItemCache {
+    pub fn new(previous: FxHashMap<Span, CachedItem>) -> Self {
+        Self {
+            previous,
+            next: FxHashMap::default(),
+            reused: vec![],
+        }
+    }
+
+    /// Takes the text of the item and its lines starting with line comments from the
+    /// previous format, if its subtree is unchanged.
+    pub fn take(&mut self, node: &SyntaxNode) -> Option<(String, Vec<usize>)> {
+        let span = node.span();
+        if !self.previous.get(&span)?.node.spanless_eq(node) {
+            return None;
+        }
+        let cached = self.previous.remove(&span)?;
+        self.reused.push(span);
+        Some((cached.text, cached.comment_lines))
+    }
+
+    /// Returns the items of the previous format, with those taken put back.
+    pub fn into_previous(mut self) -> FxHashMap<Span, CachedItem> {
+        for span in self.reused {
+            if let Some(item) = self.next.remove(&span) {
+                self.previous.insert(span, item);
+            }
+        }
+        self.previous
+    }
+
+    /// Keeps the text of the item for the next format.
+    pub fn insert(&mut self, node: &SyntaxNode, text: String, comment_lines: Vec<usize>) {
+        let item = CachedItem {
+            node: node.clone(),
+            text,
+            comment_lines,
+        };
+        self.next.insert(node.span(), item);
+    }
+}
+
+impl Typstyle {
+    /// Formats the source like [`Formatter::render`](crate::Formatter::render), reusing and
+    /// keeping the formatted text of its top-level items in the cache.
+    ///
+    /// The output is the same as without the cache. The cache is filled only when formatting
+    /// succeeds, and it is cleared when the configuration or the attributes of the whole
+    /// document change.
+    pub fn format_source_cached(
+        &self,
+        source: Source,
+        cache: &mut FormatCache,
+    ) -> Result<String, Error> {
+        if cache.config.as_ref() != Some(&self.config) {
+            cache.items.clear();
+            cache.config = Some(self.config.clone());
+        }
+        let formatter = self.format_source(source);
+        let document = formatter.printer.document_attrs();
+        if cache.document != document {
+            cache.items.clear();
+            cache.document = document;
+        }
+        formatter
+            .printer
+            .use_items(std::mem::take(&mut cache.items));
+        let res = formatter.render();
+        let items = formatter.printer.take_items().unwrap_or_default();
+        // Items of a failed format may have been kept as is, e.g., out of time.
+        if res.is_ok() {
+            cache.reused = items.reused.len();
+            cache.items = items.next;
+        } else {
+            cache.items = items.into_previous();
+        }
+        res
+    }
+
+    /// Replaces the UTF-8 byte range of the source with the given text, and formats it again.
+    ///
+    /// The source is reparsed incrementally, like with [`Source::edit`], so that unchanged
+    /// items keep their spans. Top-level items whose subtrees are unchanged since the cache
+    /// was filled are not formatted again. The output is the same as formatting the edited
+    /// source from scratch.
+    ///
+    /// # Panics
+    /// Panics if the range is out of bounds or not on character boundaries.
+    pub fn format_source_incremental(
+        &self,
+        source: &mut Source,
+        utf8_range: Range<usize>,
+        replacement: &str,
+        cache: &mut FormatCache,
+    ) -> Result<String, Error> {
+        source.edit(utf8_range, replacement);
+        self.format_source_cached(source.clone(), cache)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reuse_items() {
+        let t = Typstyle::default();
+        let mut source = Source::detached("#let  f( x )  =  x\n\n= Title\n\n#f( 1 )\n");
+        let mut cache = FormatCache::default();
+        let formatted = t.format_source_cached(source.clone(), &mut cache).unwrap();
+        assert_eq!(formatted, "#let f(x) = x\n\n= Title\n\n#f(1)\n");
+        assert_eq!(cache.len(), 3);
+
+        assert_eq!(cache.reused(), 0);
+
+        let formatted = t
+            .format_source_incremental(&mut source, 33..34, "2", &mut cache)
+            .unwrap();
+        assert_eq!(formatted, "#let f(x) = x\n\n= Title\n\n#f(2)\n");
+        // The binding and the heading are unchanged.
+        assert_eq!(cache.reused(), 2);
+
+        // Syntax errors keep the cache.
+        let res = t.format_source_incremental(&mut source, 0..0, "#let\n", &mut cache);
+        assert!(matches!(res, Err(Error::SyntaxError(_))));
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[test]
+    fn test_restore_items_on_failure() {
+        let t = Typstyle::default();
+        let source = Source::detached("#let  f( x )  =  x\n\n= Title\n\n#f( 1 )\n");
+        let mut cache = FormatCache::default();
+        t.format_source_cached(source.clone(), &mut cache).unwrap();
+        assert_eq!(cache.len(), 3);
+
+        // Building the document takes the items, which a failed format puts back,
+        // e.g., when it runs out of time while rendering.
+        let formatter = t.format_source(source.clone());
+        formatter
+            .printer
+            .use_items(std::mem::take(&mut cache.items));
+        formatter.build_doc().unwrap();
+        let items = formatter.printer.take_items().unwrap();
+        assert!(items.previous.is_empty());
+        cache.items = items.into_previous();
+        assert_eq!(cache.len(), 3);
+
+        let formatted = t.format_source_cached(source, &mut cache).unwrap();
+        assert_eq!(formatted, "#let f(x) = x\n\n= Title\n\n#f(1)\n");
+        assert_eq!(cache.reused(), 3);
+    }
+
+    #[test]
+    fn test_clear_items_on_document_change() {
+        let t = Typstyle::new(Config {
+            enum_marker_style: crate::EnumMarkerStyle::Auto,
+            ..Default::default()
+        });
+        let mut source = Source::detached("#[\n  1. a\n  2. b\n]\n");
+        let mut cache = FormatCache::default();
+        let formatted = t.format_source_cached(source.clone(), &mut cache).unwrap();
+        assert_eq!(formatted, "#[\n  + a\n  + b\n]\n");
+
+        // Enums are not renumbered in documents setting their start.
+        let formatted = t
+            .format_source_incremental(&mut source, 0..0, "#set enum(start: 3)\n", &mut cache)
+            .unwrap();
+        assert_eq!(formatted, "#set enum(start: 3)\n#[\n  1. a\n  2. b\n]\n");
+        assert_eq!(cache.reused(), 0);
+    }
+
+    #[test]
+    fn test_reuse_items_with_tabs() {
+        let t = Typstyle::new(Config {
+            indent_style: crate::IndentStyle::Tabs,
+            ..Default::default()
+        });
+        let mut source = Source::detached("#let f(x) = {\n  x\n}\n\n#g( 1 )\n");
+        let mut cache = FormatCache::default();
+        let formatted = t.format_source_cached(source.clone(), &mut cache).unwrap();
+        assert_eq!(formatted, "#let f(x) = {\n\tx\n}\n\n#g(1)\n");
+
+        let formatted = t
+            .format_source_incremental(&mut source, 25..26, "2", &mut cache)
+            .unwrap();
+        assert_eq!(formatted, "#let f(x) = {\n\tx\n}\n\n#g(2)\n");
+    }
+}
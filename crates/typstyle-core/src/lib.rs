@@ -1,26 +1,100 @@
 pub mod attr;
+pub mod cursor;
+pub mod edit;
 pub mod ext;
+pub mod incremental;
 pub mod liteval;
 pub mod partial;
 pub mod pretty;
 
+mod budget;
 mod config;
+mod explain;
+mod output;
 mod utils;
 
+use std::ops::Range;
+
 pub use attr::AttrStore;
-pub use config::Config;
-use pretty::{PrettyPrinter, prelude::*};
+pub use budget::Budget;
+pub use config::{
+    Config, EnumMarkerStyle, ImportSortOrder, IndentStyle, NewlineStyle, TrailingComma,
+};
+pub use explain::{Fallback, FallbackReason};
+pub use incremental::FormatCache;
+use output::OutputWriter;
+use pretty::{Context, Mode, PrettyPrinter, prelude::*};
 use thiserror::Error;
-use typst_syntax::{Source, SyntaxNode};
+use typst_syntax::{LinkedNode, Source, SyntaxKind, SyntaxNode};
 
 use crate::utils::indent_4_to_2;
 
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("The document has syntax errors")]
-    SyntaxError,
+    SyntaxError(Vec<SyntaxDiagnostic>),
     #[error("An error occurred while rendering the document")]
     RenderError,
+    #[error("Skipped: {0} budget exceeded")]
+    BudgetExceeded(Budget),
+}
+
+impl Error {
+    /// Moves the ranges of the syntax errors by the given number of bytes.
+    pub(crate) fn shifted(self, offset: usize) -> Self {
+        match self {
+            Self::SyntaxError(diagnostics) => Self::SyntaxError(
+                diagnostics
+                    .into_iter()
+                    .map(|diagnostic| diagnostic.shifted(offset))
+                    .collect(),
+            ),
+            err => err,
+        }
+    }
+}
+
+/// A syntax error reported by the parser.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxDiagnostic {
+    /// The UTF-8 byte range of the erroneous node in the given text.
+    pub range: Range<usize>,
+    /// The error message.
+    pub message: String,
+    /// Additional hints on how to fix the error.
+    pub hints: Vec<String>,
+}
+
+impl SyntaxDiagnostic {
+    /// Collects the syntax errors in the subtree, in the order they appear.
+    pub(crate) fn collect(node: &LinkedNode) -> Vec<Self> {
+        let mut diagnostics = vec![];
+        // An explicit stack is used, as documents may be nested arbitrarily deep.
+        let mut stack = vec![node.clone()];
+        while let Some(node) = stack.pop() {
+            if !node.erroneous() {
+                continue;
+            }
+            if node.kind() == SyntaxKind::Error {
+                diagnostics.extend(node.errors().into_iter().map(|error| SyntaxDiagnostic {
+                    range: node.range(),
+                    message: error.message.to_string(),
+                    hints: error.hints.iter().map(ToString::to_string).collect(),
+                }));
+            }
+            // Children are pushed in reverse, so that they are visited in order.
+            stack.extend(node.children().rev());
+        }
+        diagnostics
+    }
+
+    /// Moves the range by the given number of bytes.
+    pub(crate) fn shifted(self, offset: usize) -> Self {
+        Self {
+            range: self.range.start + offset..self.range.end + offset,
+            ..self
+        }
+    }
 }
 
 /// Main struct for Typst formatting.
@@ -35,6 +109,11 @@ impl Typstyle {
         Self { config }
     }
 
+    /// Returns the style configuration.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
     /// Prepares a text string for formatting.
     pub fn format_text(&self, text: impl Into<String>) -> Formatter<'_> {
         // We should ensure that the source tree is spanned.
@@ -43,7 +122,13 @@ impl Typstyle {
 
     /// Prepares a source for formatting.
     pub fn format_source(&self, source: Source) -> Formatter<'_> {
-        Formatter::new(self.config.clone(), source)
+        Formatter::new(self.config.clone(), source, Mode::Markup)
+    }
+
+    /// Prepares a text string for formatting in the given mode.
+    /// Code and math are parsed like with `typst_syntax::parse_code` and `parse_math`.
+    pub fn format_text_in_mode(&self, text: impl Into<String>, mode: Mode) -> Formatter<'_> {
+        Formatter::new(self.config.clone(), Source::detached(text.into()), mode)
     }
 }
 
@@ -51,13 +136,38 @@ impl Typstyle {
 pub struct Formatter<'a> {
     source: Source,
     printer: PrettyPrinter<'a>,
+    /// Whether the source starts with a byte order mark, which is removed from `source`.
+    has_bom: bool,
+    /// Whether lines of the output should end with `\r\n`.
+    is_crlf: bool,
+    /// The mode to format the text in.
+    mode: Mode,
 }
 
 impl<'a> Formatter<'a> {
-    fn new(config: Config, source: Source) -> Self {
+    fn new(config: Config, source: Source, mode: Mode) -> Self {
+        // The byte order mark should not be formatted as text.
+        let (source, has_bom) = match source.text().strip_prefix(utils::BOM) {
+            Some(text) => (Source::new(source.id(), text.to_string()), true),
+            None => (source, false),
+        };
+        let is_crlf = config.newline_style.is_crlf(source.text());
+        // Only markup can be parsed with numbered spans, so code and math are embedded in it.
+        let source = if mode == Mode::Markup {
+            source
+        } else {
+            let (prefix, suffix) = embedding(mode);
+            Source::new(source.id(), format!("{prefix}{}{suffix}", source.text()))
+        };
         let attr_store = AttrStore::new(source.root());
         let printer = PrettyPrinter::new(config, attr_store);
-        Self { source, printer }
+        Self {
+            source,
+            printer,
+            has_bom,
+            is_crlf,
+            mode,
+        }
     }
 
     /// Renders the document's pretty IR.
@@ -69,25 +179,179 @@ impl<'a> Formatter<'a> {
     /// Renders the formatted document to a string.
     pub fn render(&'a self) -> Result<String, Error> {
         let doc = self.build_doc()?;
-        let mut buf = String::new();
-        doc.render_fmt(self.printer.config().max_width, &mut buf)
-            .map_err(|_| Error::RenderError)?;
-        let result = utils::strip_trailing_whitespace(&buf);
+        let config = self.printer.config();
+        let mut writer = OutputWriter::new(config, true).with_deadline(self.printer.deadline());
+        let rendered = doc.render_raw(config.max_width, &mut writer);
+        // Writing stops with an error once the time is up.
+        self.printer.check_time()?;
+        rendered.map_err(|_| Error::RenderError)?;
+        let (text, comment_lines) = writer.finish();
+        let mut result = utils::strip_trailing_whitespace(&text);
+        if !comment_lines.is_empty() {
+            self.printer.check_time()?;
+            result = pretty::format_line_comments(&result, &comment_lines, config);
+        }
+        if self.is_crlf {
+            self.printer.check_time()?;
+            result = utils::use_crlf(&result);
+        }
+        if self.has_bom && !config.strip_bom {
+            result.insert(0, utils::BOM);
+        }
+        self.printer.check_time()?;
         Ok(result)
     }
 
+    /// Lists the nodes formatted by fallback paths, sorted by their ranges in the given text.
+    ///
+    /// These are nodes kept as is, e.g., with `@typstyle off`, tables not laid out in columns,
+    /// equations not aligned, and chains of method calls kept on one line.
+    pub fn explain(&'a self) -> Result<Vec<Fallback>, Error> {
+        self.printer.record_fallbacks();
+        self.build_doc()?;
+        let (prefix, suffix) = embedding(self.mode);
+        let text_len = self.source.text().len() - prefix.len() - suffix.len();
+        let bom_len = if self.has_bom {
+            utils::BOM.len_utf8()
+        } else {
+            0
+        };
+        let mut fallbacks = (self.printer.take_fallbacks().into_iter())
+            .filter_map(|(span, reason)| {
+                let range = self.source.range(span)?;
+                let offset = |pos: usize| pos.saturating_sub(prefix.len()).min(text_len) + bom_len;
+                Some(Fallback {
+                    range: offset(range.start)..offset(range.end),
+                    reason,
+                })
+            })
+            .collect::<Vec<_>>();
+        // Nodes may be converted more than once, e.g., when trying layouts.
+        fallbacks
+            .sort_by_key(|fallback| (fallback.range.start, fallback.range.end, fallback.reason));
+        fallbacks.dedup();
+        Ok(fallbacks)
+    }
+
     fn build_doc(&'a self) -> Result<ArenaDoc<'a>, Error> {
+        let (prefix, suffix) = embedding(self.mode);
+        let input_size = self.source.text().len() - prefix.len() - suffix.len();
+        self.printer.start_budget(input_size)?;
         let root = self.source.root();
-        if root.erroneous() {
-            return Err(Error::SyntaxError);
+        // Only top-level markup can be formatted around errors.
+        if root.erroneous()
+            && !(self.printer.config().format_erroneous && self.mode == Mode::Markup)
+        {
+            return Err(Error::SyntaxError(self.syntax_errors()));
         }
-        let markup = root.cast().unwrap();
-        let doc = self.printer.convert_markup(Default::default(), markup);
+        let ctx = Context::default().with_mode(self.mode);
+        let doc = match (self.mode, root.children().as_slice()) {
+            (Mode::Markup, _) => self.printer.convert_markup(ctx, root.cast().unwrap()),
+            // The text must be the whole body of the code block or equation.
+            (Mode::Code | Mode::CodeCont, [_, body]) | (Mode::Math, [body]) => {
+                self.printer.convert_embedded_body(ctx, body)
+            }
+            _ => return Err(Error::SyntaxError(self.syntax_errors())),
+        };
+        self.printer.check_time()?;
         Ok(doc)
     }
+
+    /// Collects the syntax errors, with ranges in the text given to the formatter.
+    fn syntax_errors(&self) -> Vec<SyntaxDiagnostic> {
+        // Code and math are parsed again on their own, as the embedding may shift or hide errors.
+        let (prefix, suffix) = embedding(self.mode);
+        let text = &self.source.text()[prefix.len()..self.source.text().len() - suffix.len()];
+        let root = match self.mode {
+            Mode::Markup => self.source.root().clone(),
+            Mode::Code | Mode::CodeCont => typst_syntax::parse_code(text),
+            Mode::Math => typst_syntax::parse_math(text),
+        };
+        let bom_len = if self.has_bom {
+            utils::BOM.len_utf8()
+        } else {
+            0
+        };
+        SyntaxDiagnostic::collect(&LinkedNode::new(&root))
+            .into_iter()
+            .map(|diagnostic| diagnostic.shifted(bom_len))
+            .collect()
+    }
+}
+
+/// Returns the text around code or math to embed it in markup.
+fn embedding(mode: Mode) -> (&'static str, &'static str) {
+    match mode {
+        Mode::Markup => ("", ""),
+        Mode::Code | Mode::CodeCont => ("#{\n", "\n}"),
+        Mode::Math => ("$\n", "\n$"),
+    }
 }
 
 /// Formats a `SyntaxNode` as a debug AST string with 2-space indentation.
 pub fn format_ast(root: &SyntaxNode) -> String {
     indent_4_to_2(&format!("{root:#?}"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs `f` on a new thread with the given stack size.
+    fn run_with_stack<T: Send + 'static>(size: usize, f: impl FnOnce() -> T + Send + 'static) -> T {
+        let thread = std::thread::Builder::new().stack_size(size);
+        thread.spawn(f).unwrap().join().unwrap()
+    }
+
+    #[test]
+    fn test_deeply_nested() {
+        // Deep enough to overflow a stack of 2 MiB if it were not grown.
+        let depth = 5000;
+        let text = format!("{}{}", "#[".repeat(depth), "]".repeat(depth));
+        // The parser recurses as well, so the tree is built and dropped on a large stack.
+        let source = run_with_stack(256 << 20, move || Source::detached(text));
+        let input = source.clone();
+        run_with_stack(2 << 20, move || {
+            // Nodes past the default depth are kept as is, and others are formatted on a grown stack.
+            for max_nesting_depth in [Config::default().max_nesting_depth, usize::MAX] {
+                let t = Typstyle::new(Config {
+                    max_nesting_depth,
+                    ..Default::default()
+                });
+                let formatted = t.format_source(input.clone()).render().unwrap();
+                assert_eq!(formatted.trim_end(), input.text());
+            }
+        });
+        run_with_stack(256 << 20, move || drop(source));
+    }
+
+    #[test]
+    fn test_max_input_size() {
+        let t = Typstyle::new(Config {
+            max_input_size: 8,
+            ..Default::default()
+        });
+        assert!(matches!(
+            t.format_text("#let  a  =  1").render(),
+            Err(Error::BudgetExceeded(Budget::InputSize))
+        ));
+        assert_eq!(t.format_text("#f( a )").render().unwrap(), "#f(a)\n");
+    }
+
+    #[test]
+    fn test_newline_style() {
+        // Line endings in strings and raw blocks are converted as well.
+        let text = "#let s = \"a\r\nb\"\r\n```\r\nc\r\n```\r\n";
+        let t = Typstyle::new(Config {
+            newline_style: NewlineStyle::Lf,
+            ..Default::default()
+        });
+        let formatted = t.format_text(text).render().unwrap();
+        assert_eq!(formatted, "#let s = \"a\nb\"\n```\nc\n```\n");
+        let t = Typstyle::new(Config {
+            newline_style: NewlineStyle::Crlf,
+            ..Default::default()
+        });
+        assert_eq!(t.format_text(formatted).render().unwrap(), text);
+    }
+}
@@ -2,8 +2,13 @@
 //!
 //! Currently, this is only used for determine table columns.
 
+use std::cell::Cell;
+
 use typst_syntax::ast::*;
 
+/// Expressions nested deeper than this are not evaluated, to bound the recursion.
+const MAX_DEPTH: usize = 64;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Value {
     None,
@@ -31,15 +36,32 @@ impl Liteval for Expr<'_> {
             Expr::None(v) => v.liteval(),
             Expr::Auto(v) => v.liteval(),
             Expr::Int(v) => v.liteval(),
-            Expr::Parenthesized(v) => v.liteval(),
+            Expr::Parenthesized(v) => nested(|| v.liteval()),
             Expr::Array(v) => v.liteval(),
-            Expr::Unary(v) => v.liteval(),
-            Expr::Binary(v) => v.liteval(),
+            Expr::Unary(v) => nested(|| v.liteval()),
+            Expr::Binary(v) => nested(|| v.liteval()),
             _ => Err(EvalError::NotSupported),
         }
     }
 }
 
+/// Evaluates a nested expression, failing if it is nested too deep.
+fn nested(f: impl FnOnce() -> EvalResult) -> EvalResult {
+    thread_local! {
+        static DEPTH: Cell<usize> = const { Cell::new(0) };
+    }
+    DEPTH.with(|depth| {
+        let current = depth.get();
+        if current >= MAX_DEPTH {
+            return Err(EvalError::NotSupported);
+        }
+        depth.set(current + 1);
+        let res = f();
+        depth.set(current);
+        res
+    })
+}
+
 impl Liteval for None<'_> {
     fn liteval(&self) -> EvalResult {
         Ok(Value::None)
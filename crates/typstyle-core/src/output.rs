@@ -0,0 +1,171 @@
+//! Writing the rendered document to a string.
+
+use std::fmt;
+
+use prettyless::Render;
+
+use crate::{Config, IndentStyle, budget::Deadline};
+
+/// The text the printer writes right before a line comment, so that it can be found.
+///
+/// It has no width, so it never changes the layout, and it is never written to the output.
+pub(crate) const LINE_COMMENT_MARK: &str = "\u{2060}\u{200B}";
+
+/// Collects the rendered document, indenting lines with tabs if configured,
+/// and finding the lines that start with line comments.
+///
+/// Spaces written alone at the start of a line are the indentation of the printer. With tabs,
+/// a tab takes the place of `tab_spaces` of them, and spaces that do not fill a whole tab
+/// are kept. Leading spaces written as part of text, e.g., in strings, raw blocks and nodes
+/// kept as is, are never replaced.
+///
+/// Line comments are found by the [`LINE_COMMENT_MARK`] written before them by the printer.
+/// Only those starting lines are reported, since trailing ones are never wrapped.
+///
+/// Unions buffer their chosen branch and write it at once, which would hide its indentation.
+/// The printer only uses partial unions, which do not buffer.
+pub(crate) struct OutputWriter {
+    buf: String,
+    /// The width of a tab, if lines are indented with tabs.
+    tab_width: Option<usize>,
+    /// Whether nothing but indentation is written since the last linebreak.
+    at_line_start: bool,
+    /// The spaces of indentation not written to `buf` yet.
+    pending_spaces: usize,
+    /// The number of linebreaks written.
+    line: usize,
+    /// The lines starting with line comments, after indentation.
+    comment_lines: Vec<usize>,
+    /// The time when writing should stop, checked at each linebreak.
+    deadline: Deadline,
+}
+
+impl OutputWriter {
+    /// Creates a writer for output starting at the beginning of a line, or after some text.
+    pub fn new(config: &Config, at_line_start: bool) -> Self {
+        Self {
+            buf: String::new(),
+            tab_width: (config.indent_style == IndentStyle::Tabs && config.tab_spaces > 0)
+                .then_some(config.tab_spaces),
+            at_line_start,
+            pending_spaces: 0,
+            line: 0,
+            comment_lines: vec![],
+            deadline: Deadline::default(),
+        }
+    }
+
+    /// Fails writing once the deadline has passed.
+    pub fn with_deadline(mut self, deadline: Deadline) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Keeps indentation as spaces, for output that is measured and printed again.
+    pub fn with_spaces(mut self) -> Self {
+        self.tab_width = None;
+        self
+    }
+
+    /// Returns the output, and the indices of its lines starting with line comments.
+    /// Indentation at the end is dropped, as it trails.
+    pub fn finish(self) -> (String, Vec<usize>) {
+        (self.buf, self.comment_lines)
+    }
+
+    fn write_indent(&mut self) {
+        let spaces = std::mem::take(&mut self.pending_spaces);
+        let (tabs, spaces) = match self.tab_width {
+            Some(tab_width) => (spaces / tab_width, spaces % tab_width),
+            None => (0, spaces),
+        };
+        self.buf.extend(std::iter::repeat_n('\t', tabs));
+        self.buf.extend(std::iter::repeat_n(' ', spaces));
+    }
+}
+
+impl Render for OutputWriter {
+    type Error = fmt::Error;
+
+    fn write_str(&mut self, s: &str) -> Result<usize, Self::Error> {
+        self.write_str_all(s).map(|_| s.len())
+    }
+
+    fn write_str_all(&mut self, s: &str) -> Result<(), Self::Error> {
+        if s == "\n" {
+            if self.deadline.has_passed() {
+                return Err(fmt::Error);
+            }
+            // Indentation before a linebreak trails.
+            self.pending_spaces = 0;
+            self.buf.push('\n');
+            self.at_line_start = true;
+            self.line += 1;
+        } else if s == LINE_COMMENT_MARK {
+            if self.at_line_start {
+                self.comment_lines.push(self.line);
+            }
+        } else if self.at_line_start && s.bytes().all(|b| b == b' ') {
+            self.pending_spaces += s.len();
+        } else {
+            self.write_indent();
+            self.buf.push_str(s);
+            // Reused items are written with their indentation, which may have tabs.
+            self.at_line_start &= s.bytes().all(|b| b == b' ' || b == b'\t');
+            self.line += s.bytes().filter(|&b| b == b'\n').count();
+        }
+        Ok(())
+    }
+
+    fn fail_doc(&self) -> Self::Error {
+        fmt::Error
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Config, IndentStyle, Typstyle};
+
+    fn format_with_tabs(text: &str, tab_spaces: usize) -> String {
+        let config = Config {
+            indent_style: IndentStyle::Tabs,
+            tab_spaces,
+            ..Default::default()
+        };
+        Typstyle::new(config).format_text(text).render().unwrap()
+    }
+
+    #[test]
+    fn test_indent_with_tabs() {
+        let s = "#let f(x) = {\n    for i in range(5) {\n        x = \"a\n    b\"\n    }\n}\n";
+        assert_eq!(
+            format_with_tabs(s, 4),
+            "#let f(x) = {\n\tfor i in range(5) {\n\t\tx = \"a\n    b\"\n\t}\n}\n"
+        );
+        let s = "#{\n  ```\n    code\n  ```\n}\n";
+        assert_eq!(format_with_tabs(s, 2), "#{\n\t```\n\t  code\n\t```\n}\n");
+        let s = "#{\n  // @typstyle off\n  let  x = (\n     1)\n}\n";
+        assert_eq!(
+            format_with_tabs(s, 2),
+            "#{\n\t// @typstyle off\n\tlet  x = (\n     1)\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_line_comment_marks_not_written() {
+        let config = Config {
+            wrap_comments: true,
+            format_code_in_raw_blocks: true,
+            align_term_descriptions: true,
+            ..Default::default()
+        };
+        let t = Typstyle::new(config);
+        let s = "// a\n#let x = 1 // b\n```typ\n// c\n#f( x )\n```\n/ Term: x\n/ Long term: y\n";
+        let formatted = t.format_text(s).render().unwrap();
+        assert!(!formatted.contains(super::LINE_COMMENT_MARK));
+        assert_eq!(
+            formatted,
+            "// a\n#let x = 1 // b\n```typ\n// c\n#f(x)\n```\n/ Term:      x\n/ Long term: y\n"
+        );
+    }
+}
@@ -12,8 +12,9 @@ use itertools::Itertools;
 use typst_syntax::{LinkedNode, Source, Span, SyntaxKind, SyntaxNode, ast::*};
 
 use crate::{
-    AttrStore, Error, PrettyPrinter, Typstyle,
-    pretty::Mode,
+    AttrStore, Config, Error, PrettyPrinter, SyntaxDiagnostic, Typstyle,
+    output::OutputWriter,
+    pretty::{self, Mode},
     utils::{self, indent_4_to_2},
 };
 
@@ -51,23 +52,70 @@ impl Typstyle {
         source: Source,
         utf8_range: Range<usize>,
     ) -> Result<RangeResult, Error> {
+        // The byte order mark should not be formatted as text. It is never changed here.
+        if let Some(text) = source.text().strip_prefix(utils::BOM) {
+            let bom_len = utils::BOM.len_utf8();
+            let source = Source::new(source.id(), text.to_string());
+            let range =
+                utf8_range.start.saturating_sub(bom_len)..utf8_range.end.saturating_sub(bom_len);
+            let res = self
+                .format_source_range(source, range)
+                .map_err(|err| err.shifted(bom_len))?;
+            return Ok(RangeResult {
+                source_range: res.source_range.start + bom_len..res.source_range.end + bom_len,
+                content: res.content,
+            });
+        }
+
         let trimmed_range = trim_range(source.text(), utf8_range);
         let (node, mode) = get_node_and_mode_for_range(&source, trimmed_range.clone())?;
+        let is_root = node.parent().is_none();
 
         let Some((node, node_range)) = refine_node_range(node, trimmed_range.clone()) else {
             return Ok(RangeResult::empty(trimmed_range.start)); // No edit
         };
+        let config = self.config_for_range(&source, is_root, &node_range);
 
         let attrs = AttrStore::new(&node); // Here we only compute the attributes of that subtree.
-        let printer = PrettyPrinter::new(self.config.clone(), attrs);
+        let printer = PrettyPrinter::new(config, attrs);
         let doc = printer.try_convert_with_mode(&node, mode)?;
 
         // Infer indent from context.
-        let indent = utils::count_spaces_after_last_newline(source.text(), node_range.start);
-        let text = doc
+        let indent = utils::count_indent_after_last_newline(
+            source.text(),
+            node_range.start,
+            self.config.tab_spaces,
+        );
+        let text_before = &source.text()[..node_range.start];
+        let line_prefix = &text_before[text_before.rfind('\n').map_or(0, |i| i + 1)..];
+        let mut writer = OutputWriter::new(
+            &self.config,
+            line_prefix.trim_start_matches([' ', '\t']).is_empty(),
+        )
+        .with_deadline(printer.deadline());
+        let rendered = doc
             .nest(indent as isize)
-            .print(self.config.max_width)
-            .to_string();
+            .render_raw(self.config.max_width, &mut writer);
+        // Writing stops with an error once the time is up.
+        printer.check_time()?;
+        rendered.map_err(|_| Error::RenderError)?;
+        let (text, comment_lines) = writer.finish();
+        let text = if comment_lines.is_empty() {
+            text
+        } else {
+            printer.check_time()?;
+            // The first line starts with the text before the range in the source.
+            let text = [line_prefix, &text].concat();
+            let mut text = pretty::format_line_comments(&text, &comment_lines, &self.config);
+            text.split_off(line_prefix.len())
+        };
+        let text = if self.config.newline_style.is_crlf(source.text()) {
+            printer.check_time()?;
+            utils::use_crlf(&text)
+        } else {
+            text
+        };
+        printer.check_time()?;
 
         Ok(RangeResult {
             source_range: node_range,
@@ -75,6 +123,28 @@ impl Typstyle {
         })
     }
 
+    /// Blank lines around blocks depend on the lines outside the range,
+    /// so they are only ensured when the whole document is formatted.
+    fn config_for_range(
+        &self,
+        source: &Source,
+        is_root: bool,
+        node_range: &Range<usize>,
+    ) -> Config {
+        let text = source.text();
+        let is_whole = is_root
+            && text[..node_range.start].trim().is_empty()
+            && text[node_range.end..].trim().is_empty();
+        if is_whole {
+            self.config.clone()
+        } else {
+            Config {
+                blank_lines_around_blocks: 0,
+                ..self.config.clone()
+            }
+        }
+    }
+
     /// Get the pretty IR for the smallest syntax node covering the given byte range.
     ///
     /// # Arguments
@@ -90,13 +160,15 @@ impl Typstyle {
     ) -> Result<RangeResult, Error> {
         let trimmed_range = trim_range(source.text(), utf8_range);
         let (node, mode) = get_node_and_mode_for_range(&source, trimmed_range.clone())?;
+        let is_root = node.parent().is_none();
 
         let Some((node, node_range)) = refine_node_range(node, trimmed_range.clone()) else {
             return Ok(RangeResult::empty(trimmed_range.start)); // No edit
         };
+        let config = self.config_for_range(&source, is_root, &node_range);
 
         let attrs = AttrStore::new(&node);
-        let printer = PrettyPrinter::new(self.config.clone(), attrs);
+        let printer = PrettyPrinter::new(config, attrs);
         let doc = printer.try_convert_with_mode(&node, mode)?;
 
         let ir = indent_4_to_2(&format!("{doc:#?}"));
@@ -170,9 +242,13 @@ fn get_node_and_mode_for_range(
     source: &Source,
     utf8_range: Range<usize>,
 ) -> Result<(LinkedNode<'_>, Mode), Error> {
-    get_node_cover_range(source, utf8_range)
-        .filter(|(node, _)| !node.erroneous())
-        .ok_or(Error::SyntaxError)
+    match get_node_cover_range(source, utf8_range) {
+        Some((node, _)) if node.erroneous() => {
+            Err(Error::SyntaxError(SyntaxDiagnostic::collect(&node)))
+        }
+        Some(res) => Ok(res),
+        None => Err(Error::SyntaxError(vec![])),
+    }
 }
 
 /// Get a Markup/Expr/Pattern node from source with minimal span that covering the given range.
@@ -182,36 +258,48 @@ fn get_node_cover_range(source: &Source, range: Range<usize>) -> Option<(LinkedN
         .and_then(|(span, mode)| source.find(span).map(|node| (node, mode)))
 }
 
-fn get_node_cover_range_impl(
+fn get_node_cover_range_impl<'a>(
     range: Range<usize>,
-    node: LinkedNode<'_>,
+    node: LinkedNode<'a>,
     mode: Mode,
 ) -> Option<(Span, Mode)> {
-    let mode = match node.kind() {
-        SyntaxKind::Markup => Mode::Markup,
-        SyntaxKind::CodeBlock => Mode::Code,
-        SyntaxKind::Equation => Mode::Math,
-        _ => mode,
+    let covers = |node: &LinkedNode| {
+        let node_range = node.range();
+        node_range.start <= range.start && node_range.end >= range.end
+    };
+    let enter = |node: LinkedNode<'a>, mode: Mode| {
+        let mode = match node.kind() {
+            SyntaxKind::Markup => Mode::Markup,
+            SyntaxKind::CodeBlock => Mode::Code,
+            SyntaxKind::Equation => Mode::Math,
+            _ => mode,
+        };
+        (node.children(), node, mode)
     };
 
-    // First, try to find a child node that covers the range
-    for child in node.children() {
-        if let Some(res) = get_node_cover_range_impl(range.clone(), child, mode) {
-            return Some(res);
+    // Nodes are visited in postorder, so that children covering the range are preferred.
+    // An explicit stack is used, as documents may be nested arbitrarily deep.
+    let mut stack = vec![enter(node, mode)];
+    while let Some((children, _, mode)) = stack.last_mut() {
+        // Only children covering the range can have descendants covering it.
+        if let Some(child) = children.find(|child| covers(child)) {
+            let mode = *mode;
+            stack.push(enter(child, mode));
+            continue;
+        }
+        let (_, node, mode) = stack.pop().expect("frame");
+        if covers(&node)
+            && (node.is::<Markup>()
+                || node.is::<Code>()
+                || node.is::<Math>()
+                || node.is::<Expr>()
+                || node.is::<Pattern>())
+        {
+            // It returns span to avoid problems with borrowing.
+            return Some((node.span(), mode));
         }
     }
-
-    // If no child covers the range, check if this node covers it
-    let node_range = node.range();
-    (node_range.start <= range.start
-        && node_range.end >= range.end
-        && (node.is::<Markup>()
-            || node.is::<Code>()
-            || node.is::<Math>()
-            || node.is::<Expr>()
-            || node.is::<Pattern>()))
-    .then(|| (node.span(), mode))
-    // It returns span to avoid problems with borrowing.
+    None
 }
 
 #[cfg(test)]
@@ -219,6 +307,7 @@ mod tests {
     use insta::{assert_debug_snapshot, assert_snapshot};
 
     use super::*;
+    use crate::IndentStyle;
 
     fn test(content: &str, lc_range: Range<(usize, usize)>) -> RangeResult {
         let source = Source::detached(content);
@@ -307,6 +396,22 @@ mod tests {
         assert_snapshot!(res.content, @r#""1""#);
     }
 
+    #[test]
+    fn cover_code_with_tabs() {
+        let source = Source::detached("#{\n\tif true {\n\t\t(1,2)\n\t}\n}");
+        let range = source.lines().line_column_to_byte(1, 1).unwrap()
+            ..source.lines().line_column_to_byte(3, 2).unwrap();
+
+        let t = Typstyle::new(Config {
+            indent_style: IndentStyle::Tabs,
+            ..Default::default()
+        });
+        let res = t.format_source_range(source, range).unwrap();
+
+        assert_debug_snapshot!(res.source_range, @"4..24");
+        assert_eq!(res.content, "if true {\n\t\t(1, 2)\n\t}");
+    }
+
     #[test]
     fn cover_math() {
         let res = test(
@@ -324,4 +429,30 @@ $"""#,
         tan(z)
         ");
     }
+
+    #[test]
+    fn cover_erroneous() {
+        let diagnostics = |text: &str, range: Range<usize>| {
+            let err = Typstyle::default()
+                .format_source_range(Source::detached(text), range)
+                .unwrap_err();
+            let Error::SyntaxError(diagnostics) = err else {
+                panic!("expected a syntax error, got {err:?}");
+            };
+            diagnostics
+                .into_iter()
+                .map(|diagnostic| (diagnostic.range, diagnostic.message))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            diagnostics("#(1+1)\n#let", 7..11),
+            [(11..11, "expected pattern".to_string())]
+        );
+        // The ranges refer to the text with the byte order mark.
+        assert_eq!(
+            diagnostics("\u{feff}#let", 3..7),
+            [(7..7, "expected pattern".to_string())]
+        );
+    }
 }
@@ -7,7 +7,7 @@ use super::{
     prelude::*,
     util::has_comment_children,
 };
-use crate::PrettyPrinter;
+use crate::{FallbackReason, PrettyPrinter};
 
 impl<'a> PrettyPrinter<'a> {
     pub(super) fn convert_field_access(
@@ -38,9 +38,6 @@ impl<'a> PrettyPrinter<'a> {
         ctx: Context,
         node: &'a SyntaxNode,
     ) -> Option<ArenaDoc<'a>> {
-        if ctx.break_suppressed {
-            return None;
-        }
         let mut dot_num = 0;
         let mut call_num = 0;
         let mut has_comment = false;
@@ -55,6 +52,12 @@ impl<'a> PrettyPrinter<'a> {
                 has_comment = true;
             }
         }
+        if ctx.break_suppressed {
+            if dot_num > 1 && call_num > 0 {
+                self.explain_fallback(node, FallbackReason::DotChainInline);
+            }
+            return None;
+        }
         if dot_num > 1 && call_num == 1 && !has_comment {
             if let Some(res) = self.try_convert_dot_chain_plain(ctx, chain) {
                 return Some(res);
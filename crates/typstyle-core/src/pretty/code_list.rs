@@ -1,4 +1,4 @@
-use typst_syntax::{SyntaxKind, ast::*};
+use typst_syntax::{SyntaxKind, SyntaxNode, ast::*};
 
 use super::{
     Context, Mode, PrettyPrinter,
@@ -56,14 +56,47 @@ impl<'a> PrettyPrinter<'a> {
     /// Only used for partial format.
     pub(super) fn convert_code(&'a self, ctx: Context, code: Code<'a>) -> ArenaDoc<'a> {
         let ctx = ctx.with_mode(Mode::Code);
+        self.convert_code_like(ctx, code.to_untyped().children())
+    }
 
+    /// Converts the body of a code block or an equation without its delimiters,
+    /// keeping the comments around the body. The context should be in code or math mode.
+    ///
+    /// Code and math snippets are embedded this way to be parsed with numbered spans.
+    pub(crate) fn convert_embedded_body(
+        &'a self,
+        ctx: Context,
+        node: &'a SyntaxNode,
+    ) -> ArenaDoc<'a> {
+        let mut nodes = vec![];
+        for child in node.children() {
+            if let Some(code) = child.cast::<Code>() {
+                nodes.extend(code.to_untyped().children());
+            } else if !matches!(
+                child.kind(),
+                SyntaxKind::LeftBrace | SyntaxKind::RightBrace | SyntaxKind::Dollar
+            ) {
+                nodes.push(child);
+            }
+        }
+        // Spaces around the body belong to the embedding.
+        let is_space = |node: &&&SyntaxNode| node.kind() == SyntaxKind::Space;
+        let start = nodes.iter().take_while(is_space).count();
+        let end = nodes.len() - nodes[start..].iter().rev().take_while(is_space).count();
+        self.convert_code_like(ctx, nodes[start..end].iter().copied())
+    }
+
+    /// Converts expressions on separate lines, with comments and blank lines kept.
+    fn convert_code_like(
+        &'a self,
+        ctx: Context,
+        nodes: impl Iterator<Item = &'a SyntaxNode>,
+    ) -> ArenaDoc<'a> {
         ListStylist::new(self)
             .disallow_front_comment()
             .with_fold_style(FoldStyle::Never)
             .keep_linebreak(self.config.blank_lines_upper_bound)
-            .process_iterable(ctx, code.to_untyped().children(), |ctx, expr| {
-                self.convert_expr(ctx, expr)
-            })
+            .process_iterable(ctx, nodes, |ctx, expr| self.convert_expr(ctx, expr))
             .print_doc(ListStyle {
                 separator: "",
                 delim: ("", ""),
@@ -132,6 +165,7 @@ impl<'a> PrettyPrinter<'a> {
             .print_doc(ListStyle {
                 add_trailing_sep_single: is_explicit,
                 add_trailing_sep_always: ends_with_comma,
+                omit_trailing_sep: self.omits_trailing_comma(),
                 delim: if is_explicit { ("(", ")") } else { ("", "") },
                 tight_delim: !is_explicit,
                 no_indent: !is_explicit,
@@ -152,6 +186,7 @@ impl<'a> PrettyPrinter<'a> {
             })
             .print_doc(ListStyle {
                 delim: (if all_spread { "(:" } else { "(" }, ")"),
+                omit_trailing_sep: self.omits_trailing_comma(),
                 ..Default::default()
             })
     }
@@ -176,6 +211,7 @@ impl<'a> PrettyPrinter<'a> {
             .always_fold_if(|| only_one_pattern)
             .print_doc(ListStyle {
                 add_trailing_sep_single: only_one_pattern,
+                omit_trailing_sep: self.omits_trailing_comma(),
                 ..Default::default()
             })
     }
@@ -206,6 +242,7 @@ impl<'a> PrettyPrinter<'a> {
             .always_fold_if(|| is_single_simple)
             .print_doc(ListStyle {
                 omit_delim_single: is_single_simple,
+                omit_trailing_sep: self.omits_trailing_comma(),
                 ..Default::default()
             })
     }
@@ -65,8 +65,12 @@ impl<'a> PrettyPrinter<'a> {
         match pattern {
             Pattern::Normal(n) => self.convert_expr(ctx, n),
             Pattern::Placeholder(_) => self.convert_literal("_"),
-            Pattern::Destructuring(d) => self.convert_destructuring(ctx, d),
-            Pattern::Parenthesized(p) => self.convert_parenthesized(ctx, p),
+            Pattern::Destructuring(d) => {
+                self.convert_nested(d.to_untyped(), || self.convert_destructuring(ctx, d))
+            }
+            Pattern::Parenthesized(p) => {
+                self.convert_nested(p.to_untyped(), || self.convert_parenthesized(ctx, p))
+            }
         }
     }
 
@@ -1,10 +1,36 @@
+use std::ops::Range;
+
+use itertools::Itertools;
 use typst_syntax::{SyntaxKind, SyntaxNode};
+use unicode_width::UnicodeWidthStr;
 
-use super::{Context, PrettyPrinter, prelude::*};
+use super::{Context, PrettyPrinter, prelude::*, text::is_enum_marker};
+use crate::{Config, NewlineStyle, Typstyle, output::LINE_COMMENT_MARK};
 
 impl<'a> PrettyPrinter<'a> {
     pub(super) fn convert_comment(&'a self, _ctx: Context, node: &'a SyntaxNode) -> ArenaDoc<'a> {
-        comment(&self.arena, node)
+        if node.kind() == SyntaxKind::LineComment {
+            (self.line_comment_mark() + line_comment(&self.arena, node)).as_line_suffix()
+        } else {
+            comment(&self.arena, node)
+        }
+    }
+
+    /// Marks the line comment following it, so that it can be formatted after rendering
+    /// by [`format_line_comments`].
+    pub(super) fn line_comment_mark(&'a self) -> ArenaDoc<'a> {
+        if self.config.wrap_comments || self.config.format_code_in_doc_comments {
+            self.arena.text(LINE_COMMENT_MARK)
+        } else {
+            self.arena.nil()
+        }
+    }
+
+    /// Converts a rendered line starting with a line comment, marking the line
+    /// so that its comment is formatted like others.
+    pub(super) fn convert_comment_line(&'a self, line: &str) -> ArenaDoc<'a> {
+        // The indentation is kept in the text, like in other rendered lines.
+        self.line_comment_mark() + self.arena.text(line.to_string())
     }
 }
 
@@ -94,10 +120,312 @@ fn align_multiline_simple<'a>(arena: &'a Arena<'a>, text: &'a str) -> ArenaDoc<'
     doc.nest(1).align()
 }
 
+/// A line comment exclusive to its line.
+struct CommentLine<'a> {
+    /// The index of the line.
+    line: usize,
+    /// The byte range of the comment.
+    range: Range<usize>,
+    /// The whitespace before the comment.
+    indent: &'a str,
+    /// The leading slashes.
+    prefix: &'a str,
+    /// The text after the prefix.
+    body: &'a str,
+}
+
+enum CommentLineKind {
+    /// Kept as is, and never joined with other lines.
+    Fixed,
+    /// Starts a new paragraph, like a list item.
+    Start,
+    /// Continues the paragraph.
+    Normal,
+}
+
+/// Formats runs of consecutive line comments starting the given lines of `text` after
+/// indentation, according to `wrap_comments` and `format_code_in_doc_comments`.
+///
+/// With `wrap_comments`, paragraphs are reflowed to fit within `max_width`, only when one of
+/// their lines is too long. Blank comment lines, indented code, fenced code, URLs and
+/// `@typstyle` directives are kept as is.
+///
+/// With `format_code_in_doc_comments`, Typst code fenced in doc comments is formatted,
+/// unless it has syntax errors.
+///
+/// The lines are found by the printer, so comments in nodes with formatting disabled
+/// are never changed.
+pub fn format_line_comments(text: &str, comment_lines: &[usize], config: &Config) -> String {
+    let lines = collect_comment_lines(text, comment_lines);
+
+    let mut edits = vec![];
+    let flush = |paragraph: &mut Vec<&CommentLine>, edits: &mut Vec<_>| {
+        if config.wrap_comments
+            && paragraph
+                .iter()
+                .any(|line| comment_line_width(text, line, config) > config.max_width)
+        {
+            edits.push(reflow_paragraph(paragraph, config));
+        }
+        paragraph.clear();
+    };
+
+    let mut paragraph = vec![];
+    // The backtick count, language tag and lines of the open code fence.
+    let mut fence: Option<(usize, &str, Vec<&CommentLine>)> = None;
+    for (i, line) in lines.iter().enumerate() {
+        let is_adjacent = i > 0 && {
+            let prev = &lines[i - 1];
+            prev.line + 1 == line.line && prev.indent == line.indent && prev.prefix == line.prefix
+        };
+        if !is_adjacent {
+            flush(&mut paragraph, &mut edits);
+            fence = None;
+        }
+
+        let content = line.body.strip_prefix(' ').unwrap_or_default().trim_start();
+        let ticks = content.len() - content.trim_start_matches('`').len();
+        if ticks >= 3 {
+            flush(&mut paragraph, &mut edits);
+            let info = content[ticks..].trim();
+            match fence.take() {
+                Some((open_ticks, lang, code_lines)) if ticks >= open_ticks && info.is_empty() => {
+                    if config.format_code_in_doc_comments
+                        && let Some(edit) = format_fenced_code(lang, &code_lines, config)
+                    {
+                        edits.push(edit);
+                    }
+                }
+                Some(mut open) => {
+                    open.2.push(line); // not a closing fence
+                    fence = Some(open);
+                }
+                None => fence = Some((ticks, info, vec![])),
+            }
+            continue;
+        }
+        if let Some((_, _, code_lines)) = &mut fence {
+            code_lines.push(line);
+            continue;
+        }
+
+        // Lines indented to the text of a list item continue it.
+        let hang = paragraph
+            .first()
+            .map_or(0, |first| hanging_indent(first.body));
+        if hang > 0 && continues_hanging(line.body, hang) {
+            paragraph.push(line);
+            continue;
+        }
+        match get_comment_line_kind(line.body) {
+            CommentLineKind::Fixed => flush(&mut paragraph, &mut edits),
+            CommentLineKind::Start => {
+                flush(&mut paragraph, &mut edits);
+                paragraph.push(line);
+            }
+            CommentLineKind::Normal => paragraph.push(line),
+        }
+    }
+    flush(&mut paragraph, &mut edits);
+
+    let mut res = String::with_capacity(text.len());
+    let mut pos = 0;
+    for (edit_range, replacement) in edits {
+        res.push_str(&text[pos..edit_range.start]);
+        res.push_str(&replacement);
+        pos = edit_range.end;
+    }
+    res.push_str(&text[pos..]);
+    res
+}
+
+/// Collects the line comments starting the given lines of `text` after indentation.
+fn collect_comment_lines<'a>(text: &'a str, comment_lines: &[usize]) -> Vec<CommentLine<'a>> {
+    let mut comment_lines = comment_lines.iter().copied().peekable();
+    let mut lines = vec![];
+    let mut line_start = 0;
+    for (i, line) in text.split_inclusive('\n').enumerate() {
+        let line_end = line_start + line.len();
+        if comment_lines.next_if_eq(&i).is_some() {
+            let line = line.trim_end();
+            let comment = line.trim_start_matches([' ', '\t']);
+            let body = comment.trim_start_matches('/');
+            let indent_len = line.len() - comment.len();
+            lines.push(CommentLine {
+                line: i,
+                range: line_start + indent_len..line_start + line.len(),
+                indent: &line[..indent_len],
+                prefix: &comment[..comment.len() - body.len()],
+                body,
+            });
+        }
+        line_start = line_end;
+    }
+    lines
+}
+
+fn get_comment_line_kind(body: &str) -> CommentLineKind {
+    match body.strip_prefix(' ') {
+        Some(content) => get_content_kind(content),
+        None => CommentLineKind::Fixed,
+    }
+}
+
+/// Gets the kind of a line by its text after the prefix and a space.
+fn get_content_kind(content: &str) -> CommentLineKind {
+    if content.is_empty()
+        || content.starts_with([' ', '\t'])
+        || content.contains("@typstyle")
+        || content.contains("://")
+    {
+        CommentLineKind::Fixed
+    } else if starts_paragraph(content) {
+        CommentLineKind::Start
+    } else {
+        CommentLineKind::Normal
+    }
+}
+
+/// Returns the indent of lines continuing a list or enum item, like 2 for `- item`,
+/// or 0 if the line does not start one.
+fn hanging_indent(body: &str) -> usize {
+    let content = body.strip_prefix(' ').unwrap_or_default();
+    let marker = content.split_whitespace().next().unwrap_or_default();
+    if matches!(marker, "-" | "+" | "*") || is_enum_marker(marker) {
+        marker.width() + 1
+    } else {
+        0
+    }
+}
+
+/// Whether the line is indented by exactly `hang` and continues the paragraph.
+fn continues_hanging(body: &str, hang: usize) -> bool {
+    let Some(content) = body.strip_prefix(' ') else {
+        return false;
+    };
+    let text = content.trim_start_matches(' ');
+    content.len() - text.len() == hang && matches!(get_content_kind(text), CommentLineKind::Normal)
+}
+
+/// Whether the text looks like the start of a list item or other block in the comment.
+fn starts_paragraph(content: &str) -> bool {
+    let first = content.split_whitespace().next().unwrap_or_default();
+    matches!(first, "-" | "+" | "*" | "->" | "/" | ">")
+        || first.starts_with(['#', '=', '@'])
+        || is_enum_marker(first)
+}
+
+fn comment_line_width(text: &str, line: &CommentLine, config: &Config) -> usize {
+    indent_width(line.indent, config) + text[line.range.clone()].width()
+}
+
+fn indent_width(indent: &str, config: &Config) -> usize {
+    indent
+        .chars()
+        .map(|c| if c == '\t' { config.tab_spaces } else { 1 })
+        .sum()
+}
+
+/// Formats Typst code fenced in doc comments. Returns the range to replace and the new text.
+fn format_fenced_code(
+    lang: &str,
+    code_lines: &[&CommentLine],
+    config: &Config,
+) -> Option<(Range<usize>, String)> {
+    let (first, last) = (code_lines.first()?, code_lines.last()?);
+    if first.prefix != "///" || !matches!(lang, "example" | "typ") {
+        return None;
+    }
+    let mut code = String::new();
+    for line in code_lines {
+        if !line.body.is_empty() {
+            code.push_str(line.body.strip_prefix(' ')?);
+        }
+        code.push('\n');
+    }
+
+    let max_width = config
+        .max_width
+        .saturating_sub(indent_width(first.indent, config) + first.prefix.len() + 1);
+    let config = Config {
+        max_width,
+        newline_style: NewlineStyle::Lf,
+        ..config.clone()
+    };
+    let t = Typstyle::new(config);
+    let formatted = t.format_text(code).render().ok()?;
+
+    let sep = format!("\n{}", first.indent);
+    let replacement = formatted
+        .lines()
+        .map(|line| {
+            if line.is_empty() {
+                first.prefix.to_string()
+            } else {
+                format!("{} {line}", first.prefix)
+            }
+        })
+        .join(&sep);
+    Some((first.range.start..last.range.end, replacement))
+}
+
+/// Fills the words of the paragraph greedily into lines. Returns the range to replace and the new text.
+///
+/// Lines continuing a list item are indented to its text.
+fn reflow_paragraph(paragraph: &[&CommentLine], config: &Config) -> (Range<usize>, String) {
+    let first = paragraph[0];
+    let last = paragraph[paragraph.len() - 1];
+    let hang = hanging_indent(first.body);
+    let mut budget = config
+        .max_width
+        .saturating_sub(indent_width(first.indent, config) + first.prefix.len() + 1);
+
+    let mut lines = vec![];
+    let mut current = String::new();
+    let mut current_width = 0;
+    for word in paragraph
+        .iter()
+        .flat_map(|line| line.body.split_whitespace())
+    {
+        let width = word.width();
+        // Never break before words that may start a new paragraph or a code fence.
+        if !current.is_empty()
+            && current_width + 1 + width > budget
+            && !starts_paragraph(word)
+            && !word.starts_with("```")
+        {
+            if lines.is_empty() {
+                budget = budget.saturating_sub(hang);
+            }
+            lines.push(std::mem::take(&mut current));
+            current_width = 0;
+        }
+        if !current.is_empty() {
+            current.push(' ');
+            current_width += 1;
+        }
+        current.push_str(word);
+        current_width += width;
+    }
+    lines.push(current);
+
+    let sep = format!("\n{}{} {}", first.indent, first.prefix, " ".repeat(hang));
+    let replacement = format!("{} {}", first.prefix, lines.join(&sep));
+    (first.range.start..last.range.end, replacement)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Finds the lines starting with line comments, like the printer does.
+    fn comment_lines(text: &str) -> Vec<usize> {
+        (text.lines().enumerate())
+            .filter(|(_, line)| line.trim_start().starts_with("//"))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
     #[test]
     fn test_align() {
         let cmt = "/* 0
@@ -121,6 +449,79 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_format_line_comments_wrap() {
+        let text = "#let x = 1 // trailing comments are not exclusive to their lines and are kept
+  // This is a long comment that should be wrapped at the width.
+  // Short line.
+  //
+  // - A list item starts a new paragraph.
+  //     indented code is kept as is even if it is very long
+  // See https://example.com/a/very/long/url/that/cannot/be/wrapped
+";
+        let config = Config {
+            wrap_comments: true,
+            ..Config::new().with_width(30)
+        };
+        let result = format_line_comments(text, &comment_lines(text), &config);
+        assert_eq!(
+            result,
+            "#let x = 1 // trailing comments are not exclusive to their lines and are kept
+  // This is a long comment
+  // that should be wrapped at
+  // the width. Short line.
+  //
+  // - A list item starts a
+  //   new paragraph.
+  //     indented code is kept as is even if it is very long
+  // See https://example.com/a/very/long/url/that/cannot/be/wrapped
+"
+        );
+        // Already wrapped.
+        assert_eq!(
+            format_line_comments(&result, &comment_lines(&result), &config),
+            result
+        );
+    }
+
+    #[test]
+    fn test_format_line_comments_code() {
+        let text = "/// Adds one.
+///
+/// ```example
+/// #let  x=f( 1 )
+///
+/// #x
+/// ```
+///
+/// ```typ
+/// #let (
+/// ```
+#let f(x) = x + 1
+";
+        let config = Config {
+            format_code_in_doc_comments: true,
+            ..Default::default()
+        };
+        let result = format_line_comments(text, &comment_lines(text), &config);
+        assert_eq!(
+            result,
+            "/// Adds one.
+///
+/// ```example
+/// #let x = f(1)
+///
+/// #x
+/// ```
+///
+/// ```typ
+/// #let (
+/// ```
+#let f(x) = x + 1
+"
+        );
+    }
+
     #[test]
     fn test_align2() {
         let cmt = "/* 0
@@ -1,4 +1,4 @@
-#[derive(Default, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Context {
     pub mode: Mode,
     pub break_suppressed: bool,
@@ -42,7 +42,7 @@ impl Context {
     }
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Mode {
     #[default]
     Markup,
@@ -72,7 +72,7 @@ impl Mode {
     }
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum AlignMode {
     #[default]
     Auto,
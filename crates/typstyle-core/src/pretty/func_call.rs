@@ -126,6 +126,7 @@ impl<'a> PrettyPrinter<'a> {
                 child.cast().map(|arg| self.convert_arg(ctx, arg))
             })
             .print_doc(ListStyle {
+                omit_trailing_sep: self.omits_trailing_comma(),
                 ..Default::default()
             })
     }
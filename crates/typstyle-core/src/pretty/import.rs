@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::{cmp::Ordering, collections::HashSet};
 
 use typst_syntax::{SyntaxKind, SyntaxNode, ast::*};
 
@@ -11,6 +11,7 @@ use super::{
     prelude::*,
     util::is_comment_node,
 };
+use crate::ImportSortOrder;
 
 impl<'a> PrettyPrinter<'a> {
     pub(super) fn convert_import(&'a self, ctx: Context, import: ModuleImport<'a>) -> ArenaDoc<'a> {
@@ -87,8 +88,7 @@ impl<'a> PrettyPrinter<'a> {
             && import_items_nodes.iter().all(|node| !is_comment_node(node))
             && check_import_name_duplication(&import_items_nodes)
         {
-            // Sort import items by their text representation.
-            import_items_nodes.sort_by_key(|&node| node.clone().into_text());
+            sort_import_items(&mut import_items_nodes, self.config.import_sort_order);
         }
         // Note that `ImportItem` does not implement `AstNode`.
         ListStylist::new(self)
@@ -146,19 +146,90 @@ impl<'a> PrettyPrinter<'a> {
     }
 }
 
+/// Sort import items in place by the given order. Ties are broken by their text representation.
+fn sort_import_items(import_items_nodes: &mut [&SyntaxNode], order: ImportSortOrder) {
+    import_items_nodes.sort_by_cached_key(|&node| {
+        let text = node.clone().into_text();
+        let key = match order {
+            ImportSortOrder::Bytewise | ImportSortOrder::Natural => text.to_string(),
+            ImportSortOrder::CaseInsensitive => text.to_lowercase().to_string(),
+            ImportSortOrder::Alias => import_name(node).unwrap_or(text.as_str()).to_string(),
+        };
+        (SortKey { key, order }, text)
+    });
+}
+
+/// The primary key for sorting import items.
+#[derive(PartialEq, Eq)]
+struct SortKey {
+    key: String,
+    order: ImportSortOrder,
+}
+
+impl PartialOrd for SortKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SortKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if self.order == ImportSortOrder::Natural {
+            natural_cmp(&self.key, &other.key)
+        } else {
+            self.key.cmp(&other.key)
+        }
+    }
+}
+
+/// Compare two strings, treating runs of ASCII digits as numbers.
+fn natural_cmp(mut a: &str, mut b: &str) -> Ordering {
+    /// Split the string at the end of its leading digits.
+    fn split_digits(s: &str) -> (&str, &str) {
+        s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()))
+    }
+
+    loop {
+        let (ca, cb) = match (a.chars().next(), b.chars().next()) {
+            (Some(ca), Some(cb)) => (ca, cb),
+            (ca, cb) => return ca.is_some().cmp(&cb.is_some()),
+        };
+        if ca.is_ascii_digit() && cb.is_ascii_digit() {
+            let (num_a, rest_a) = split_digits(a);
+            let (num_b, rest_b) = split_digits(b);
+            // Leading zeros do not change the value.
+            let (num_a, num_b) = (num_a.trim_start_matches('0'), num_b.trim_start_matches('0'));
+            let ord = num_a.len().cmp(&num_b.len()).then_with(|| num_a.cmp(num_b));
+            if ord != Ordering::Equal {
+                return ord;
+            }
+            (a, b) = (rest_a, rest_b);
+        } else if ca != cb {
+            return ca.cmp(&cb);
+        } else {
+            (a, b) = (&a[ca.len_utf8()..], &b[cb.len_utf8()..]);
+        }
+    }
+}
+
+/// Get the name bound by an import item.
+fn import_name(node: &SyntaxNode) -> Option<&str> {
+    match node.kind() {
+        SyntaxKind::ImportItemPath => Some(node.cast::<ImportItemPath>()?.name().as_str()),
+        SyntaxKind::RenamedImportItem => {
+            Some(node.cast::<RenamedImportItem>()?.new_name().as_str())
+        }
+        _ => Option::None,
+    }
+}
+
 /// Check for duplicate import names in the given import items nodes.
 /// Returns `true` if no duplicates are found, `false` otherwise.
 fn check_import_name_duplication(import_items_nodes: &[&SyntaxNode]) -> bool {
     let mut seen = HashSet::new();
     for node in import_items_nodes.iter() {
-        let name = match node.kind() {
-            SyntaxKind::ImportItemPath => node.cast::<ImportItemPath>().unwrap().name().as_str(),
-            SyntaxKind::RenamedImportItem => node
-                .cast::<RenamedImportItem>()
-                .unwrap()
-                .new_name()
-                .as_str(),
-            _ => continue,
+        let Some(name) = import_name(node) else {
+            continue;
         };
         if !seen.insert(name) {
             return false; // Duplicate found
@@ -166,3 +237,18 @@ fn check_import_name_duplication(import_items_nodes: &[&SyntaxNode]) -> bool {
     }
     true // No duplicates found
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_natural_cmp() {
+        assert_eq!(natural_cmp("item2", "item10"), Ordering::Less);
+        assert_eq!(natural_cmp("item10", "item10"), Ordering::Equal);
+        assert_eq!(natural_cmp("item010", "item9"), Ordering::Greater);
+        assert_eq!(natural_cmp("item", "item1"), Ordering::Less);
+        assert_eq!(natural_cmp("a2b", "a2a"), Ordering::Greater);
+        assert_eq!(natural_cmp("Zeta", "alpha"), Ordering::Less);
+    }
+}
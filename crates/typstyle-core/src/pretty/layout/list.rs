@@ -50,6 +50,8 @@ pub struct ListStyle {
     pub add_trailing_sep_single: bool,
     /// Whether a trailing separator is always needed.
     pub add_trailing_sep_always: bool,
+    /// Whether to omit the trailing separator when the list breaks, unless it is needed.
+    pub omit_trailing_sep: bool,
     /// Whether can omit the delimiter if the list contains only one item.
     pub omit_delim_single: bool,
     /// Whether can omit the delimiter if the list is flat.
@@ -71,6 +73,7 @@ impl Default for ListStyle {
             add_delim_space: false,
             add_trailing_sep_single: false,
             add_trailing_sep_always: false,
+            omit_trailing_sep: false,
             omit_delim_single: false,
             omit_delim_flat: false,
             omit_delim_empty: false,
@@ -322,6 +325,11 @@ impl<'a> ListStylist<'a> {
             self.fold_style
         };
         let item_count = self.items.len();
+        // Whether the trailing separator is required by the syntax.
+        let needs_trailing_sep =
+            sty.add_trailing_sep_always || is_single && sty.add_trailing_sep_single;
+        // Whether the trailing separator is added when the list breaks.
+        let keeps_trailing_sep = needs_trailing_sep || !sty.omit_trailing_sep;
 
         let enclose_fitted = |inner: ArenaDoc<'a>| {
             if is_single && sty.omit_delim_single {
@@ -358,7 +366,14 @@ impl<'a> ListStylist<'a> {
                     match item {
                         Item::Comment(cmt) => inner += cmt + arena.hardline(),
                         Item::Commented { body, after } => {
-                            inner += body + sep.clone() + after;
+                            seen_real_items += 1;
+                            let is_last_real = seen_real_items == self.real_item_count;
+                            let follow = if !is_last_real || keeps_trailing_sep {
+                                sep.clone()
+                            } else {
+                                arena.nil()
+                            };
+                            inner += body + follow + after;
                             if !sty.tight_delim || !is_last {
                                 inner += arena.hardline();
                             }
@@ -390,9 +405,7 @@ impl<'a> ListStylist<'a> {
                             inner += body + after;
                             if !is_last_real {
                                 inner += sep.clone() + arena.space();
-                            } else if sty.add_trailing_sep_always
-                                || is_single && sty.add_trailing_sep_single
-                            {
+                            } else if needs_trailing_sep {
                                 // trailing comma for one-size array
                                 inner += sep.clone();
                             }
@@ -427,6 +440,11 @@ impl<'a> ListStylist<'a> {
                     }
                 }
                 let last = docs.pop().unwrap();
+                let trailing_sep = if keeps_trailing_sep {
+                    sep.clone()
+                } else {
+                    arena.nil()
+                };
                 let inner = if docs.is_empty() {
                     // only one item
                     let compact = if sty.add_trailing_sep_single {
@@ -434,7 +452,7 @@ impl<'a> ListStylist<'a> {
                     } else {
                         last.clone()
                     };
-                    let expanded = self.printer.block_indent(last + sep.clone());
+                    let expanded = self.printer.block_indent(last + trailing_sep);
 
                     compact.partial_union(expanded)
                 } else {
@@ -448,7 +466,7 @@ impl<'a> ListStylist<'a> {
                             docs.iter()
                                 .map(|doc| doc.clone() + sep.clone() + arena.line()),
                         ) + last
-                            + sep.clone(),
+                            + trailing_sep,
                     );
 
                     compact.partial_union(expanded)
@@ -482,11 +500,12 @@ impl<'a> ListStylist<'a> {
                             seen_real_items += 1;
                             let is_last_real = seen_real_items == self.real_item_count;
                             let follow = if let Some(after) = after {
-                                let follow_break = sep.clone() + after.clone();
-                                let follow_flat = if !is_last_real
-                                    || sty.add_trailing_sep_always
-                                    || is_single && sty.add_trailing_sep_single
-                                {
+                                let follow_break = if !is_last_real || keeps_trailing_sep {
+                                    sep.clone() + after.clone()
+                                } else {
+                                    after.clone()
+                                };
+                                let follow_flat = if !is_last_real || needs_trailing_sep {
                                     after + sep.clone()
                                 } else {
                                     after
@@ -496,11 +515,10 @@ impl<'a> ListStylist<'a> {
                                 #[allow(clippy::let_and_return)]
                                 let follow = if is_last_real && sty.tight_delim {
                                     arena.nil()
-                                } else if !is_last_real
-                                    || sty.add_trailing_sep_always
-                                    || is_single && sty.add_trailing_sep_single
-                                {
+                                } else if !is_last_real || needs_trailing_sep {
                                     sep.clone()
+                                } else if sty.omit_trailing_sep {
+                                    arena.nil()
                                 } else {
                                     sep.clone().when_group_break()
                                 };
@@ -1,12 +1,21 @@
+use itertools::Itertools;
 use prettyless::Doc;
+use rustc_hash::FxHashMap;
 use smallvec::SmallVec;
-use typst_syntax::{SyntaxKind, SyntaxNode, ast::*};
+use typst_syntax::{Source, Span, SyntaxKind, SyntaxNode, ast::*};
+use unicode_width::UnicodeWidthStr;
 
 use super::{
     Context, Mode, PrettyPrinter, layout::flow::FlowItem, prelude::*, text::is_enum_marker,
     util::is_comment_node,
 };
-use crate::{ext::StrExt, pretty::util::is_only_one_and};
+use crate::{
+    AttrStore, EnumMarkerStyle,
+    ext::StrExt,
+    output::OutputWriter,
+    pretty::util::is_only_one_and,
+    utils::{count_indent_after_last_newline, strip_trailing_whitespace},
+};
 
 #[derive(Debug, PartialEq, Eq)]
 enum MarkupScope {
@@ -58,6 +67,11 @@ impl<'a> PrettyPrinter<'a> {
         if !raw.block() && raw.lines().nth(1).is_some() {
             return self.convert_verbatim(raw);
         }
+        if self.config.format_code_in_raw_blocks
+            && let Some(doc) = self.try_convert_typst_raw(raw)
+        {
+            return doc;
+        }
 
         let mut doc = self.arena.nil();
         for child in raw.to_untyped().children() {
@@ -74,6 +88,92 @@ impl<'a> PrettyPrinter<'a> {
         doc
     }
 
+    /// Formats the code in a raw block tagged `typ`, `typc` or `typm`.
+    /// Returns `None` if the block should be kept as is.
+    fn try_convert_typst_raw(&'a self, raw: Raw<'a>) -> Option<ArenaDoc<'a>> {
+        let (mode, prefix, suffix) = match raw.lang()?.get().as_str() {
+            "typ" => (Mode::Markup, "", ""),
+            "typc" => (Mode::Code, "#{", "}"),
+            "typm" => (Mode::Math, "$", "$"),
+            _ => return None,
+        };
+        // The code must start and end on lines of its own.
+        let [open, lang, first_trimmed, inner @ .., last_trimmed, close] =
+            raw.to_untyped().children().as_slice()
+        else {
+            return None;
+        };
+        if lang.kind() != SyntaxKind::RawLang
+            || ![first_trimmed, last_trimmed]
+                .iter()
+                .all(|it| it.kind() == SyntaxKind::RawTrimmed && it.text().has_linebreak())
+        {
+            return None;
+        }
+        let mut code = String::new();
+        for child in inner {
+            match child.kind() {
+                SyntaxKind::Text => code.push_str(child.text()),
+                SyntaxKind::RawTrimmed => {
+                    code.extend(std::iter::repeat_n('\n', child.text().count_linebreaks()))
+                }
+                _ => return None,
+            }
+        }
+
+        // Wrap the code so that it is parsed in the right mode, with spans numbered.
+        let source = Source::detached(format!("{prefix}{code}{suffix}"));
+        let root = source.root();
+        if root.erroneous() {
+            return None;
+        }
+        let node = match (mode, root.children().as_slice()) {
+            (Mode::Markup, _) => root,
+            (Mode::Code, [_, wrapper]) | (Mode::Math, [wrapper]) => {
+                match wrapper.children().as_slice() {
+                    // Otherwise, some trivia would be outside the node.
+                    [_, node, _] if matches!(node.kind(), SyntaxKind::Code | SyntaxKind::Math) => {
+                        node
+                    }
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+
+        // The snippet is indented as the closing fence.
+        let indent = count_indent_after_last_newline(
+            last_trimmed.text(),
+            last_trimmed.text().len(),
+            self.config.tab_spaces,
+        );
+        let printer = PrettyPrinter::new(self.config.clone(), AttrStore::new(node));
+        let doc = printer.try_convert_with_mode(node, mode).ok()?;
+        // The snippet is indented again with the raw block, and its comments are kept as is.
+        let mut writer = OutputWriter::new(&self.config, true).with_spaces();
+        doc.render_raw(self.config.max_width.saturating_sub(indent), &mut writer)
+            .ok()?;
+        let formatted = strip_trailing_whitespace(&writer.finish().0);
+
+        // Changing the fence would change the content of the raw block.
+        let fence_len = open.text().len();
+        if formatted
+            .split(|c: char| c != '`')
+            .any(|ticks| ticks.len() >= fence_len)
+        {
+            return None;
+        }
+
+        let mut doc = self.convert_trivia_untyped(open) + self.convert_trivia_untyped(lang);
+        for line in formatted.trim_end().lines() {
+            doc += self.arena.hardline();
+            if !line.is_empty() {
+                doc += self.arena.text(line.to_string());
+            }
+        }
+        Some(doc + self.arena.hardline() + self.convert_trivia_untyped(close))
+    }
+
     pub(super) fn convert_ref(&'a self, ctx: Context, reference: Ref<'a>) -> ArenaDoc<'a> {
         let mut doc = self.arena.text("@") + self.arena.text(reference.target());
         if let Some(supplement) = reference.supplement() {
@@ -103,7 +203,7 @@ impl<'a> PrettyPrinter<'a> {
         ctx: Context,
         list_item: ListItem<'a>,
     ) -> ArenaDoc<'a> {
-        self.convert_list_item_like(ctx, list_item.to_untyped())
+        self.convert_list_item_like(ctx, list_item.to_untyped(), Option::None)
     }
 
     pub(super) fn convert_enum_item(
@@ -111,7 +211,7 @@ impl<'a> PrettyPrinter<'a> {
         ctx: Context,
         enum_item: EnumItem<'a>,
     ) -> ArenaDoc<'a> {
-        self.convert_list_item_like(ctx, enum_item.to_untyped())
+        self.convert_list_item_like(ctx, enum_item.to_untyped(), Option::None)
     }
 
     pub(super) fn convert_term_item(
@@ -119,7 +219,17 @@ impl<'a> PrettyPrinter<'a> {
         ctx: Context,
         term_item: TermItem<'a>,
     ) -> ArenaDoc<'a> {
-        let node = term_item.to_untyped();
+        self.convert_term_item_with(ctx, term_item.to_untyped(), None, None)
+    }
+
+    /// Converts a term item, reusing the docs of its term and description if converted.
+    fn convert_term_item_with(
+        &'a self,
+        ctx: Context,
+        node: &'a SyntaxNode,
+        mut term: Option<ArenaDoc<'a>>,
+        mut description: Option<ArenaDoc<'a>>,
+    ) -> ArenaDoc<'a> {
         let mut seen_term = false;
         let body = self.convert_flow_like(ctx, node, |ctx, child, _| match child.kind() {
             SyntaxKind::TermMarker => FlowItem::spaced(self.arena.text(child.text().as_str())),
@@ -138,15 +248,22 @@ impl<'a> PrettyPrinter<'a> {
             SyntaxKind::Markup => {
                 if !seen_term || !child.is_empty() {
                     // empty markup is ignored here
-                    FlowItem::spaced(self.convert_markup_impl(
-                        ctx,
-                        child.cast().expect("markup"),
-                        if !seen_term {
-                            MarkupScope::InlineItem
-                        } else {
-                            MarkupScope::Item
-                        },
-                    ))
+                    let converted = if !seen_term {
+                        term.take()
+                    } else {
+                        description.take()
+                    };
+                    FlowItem::spaced(converted.unwrap_or_else(|| {
+                        self.convert_markup_impl(
+                            ctx,
+                            child.cast().expect("markup"),
+                            if !seen_term {
+                                MarkupScope::InlineItem
+                            } else {
+                                MarkupScope::Item
+                            },
+                        )
+                    }))
                 } else {
                     FlowItem::none()
                 }
@@ -156,10 +273,133 @@ impl<'a> PrettyPrinter<'a> {
         self.indent(body)
     }
 
-    fn convert_list_item_like(&'a self, ctx: Context, item: &'a SyntaxNode) -> ArenaDoc<'a> {
+    /// Converts term items with their descriptions aligned in one column,
+    /// for runs of consecutive term items on adjacent lines.
+    fn convert_aligned_term_items(
+        &'a self,
+        ctx: Context,
+        markup: Markup<'a>,
+        docs: &mut FxHashMap<Span, ArenaDoc<'a>>,
+    ) {
+        let mut runs = vec![];
+        let mut items = vec![];
+        for node in markup.to_untyped().children() {
+            match node.kind() {
+                SyntaxKind::TermItem => {
+                    if let Some(item) = self.split_term_item(ctx, node) {
+                        if item.term_text.is_some() {
+                            items.push(item);
+                            continue;
+                        }
+                        // It ends the run, but its converted parts are still reused.
+                        docs.insert(node.span(), self.convert_split_term_item(ctx, item));
+                    }
+                }
+                SyntaxKind::Space if node.text().count_linebreaks() == 1 => continue,
+                _ => {}
+            }
+            runs.push(std::mem::take(&mut items));
+        }
+        runs.push(items);
+
+        for run in runs.into_iter().filter(|run| !run.is_empty()) {
+            self.align_term_items(ctx, run, docs);
+        }
+    }
+
+    /// Converts the term and description of a term item, and measures them.
+    ///
+    /// Returns `None` if the item can never be aligned, before converting anything.
+    fn split_term_item(&'a self, ctx: Context, node: &'a SyntaxNode) -> Option<SplitTermItem<'a>> {
+        if self.attr_store.is_format_disabled(node)
+            || node.children().any(|child| {
+                child.kind() == SyntaxKind::Space && child.text().has_linebreak()
+                    || is_comment_node(child)
+            })
+        {
+            return None;
+        }
+        let item = node.cast::<TermItem>()?;
+        let render = |doc: ArenaDoc<'a>| {
+            let mut writer = OutputWriter::new(&self.config, true).with_spaces();
+            doc.render_raw(self.config.max_width, &mut writer).ok()?;
+            Some(writer.finish().0)
+        };
+
+        let term = self.convert_markup_impl(ctx, item.term(), MarkupScope::InlineItem);
+        let term_text =
+            render(term.clone()).filter(|text| !text.is_empty() && !text.has_linebreak());
+        let description = if item.description().to_untyped().is_empty() {
+            Option::None
+        } else {
+            let doc = self.convert_markup_impl(ctx, item.description(), MarkupScope::Item);
+            let width = render(doc.clone())
+                .filter(|text| !text.has_linebreak())
+                .map(|text| text.width());
+            Some((doc, width))
+        };
+        Some(SplitTermItem {
+            node,
+            term,
+            term_text,
+            description,
+        })
+    }
+
+    /// Converts a term item without alignment, from its converted parts.
+    fn convert_split_term_item(&'a self, ctx: Context, item: SplitTermItem<'a>) -> ArenaDoc<'a> {
+        let description = item.description.map(|(doc, _)| doc);
+        self.convert_term_item_with(ctx, item.node, Some(item.term), description)
+    }
+
+    fn align_term_items(
+        &'a self,
+        ctx: Context,
+        items: Vec<SplitTermItem<'a>>,
+        docs: &mut FxHashMap<Span, ArenaDoc<'a>>,
+    ) {
+        // The width of `/ term:`.
+        let prefix_width =
+            |item: &SplitTermItem| item.term_text.as_ref().map_or(0, |term| term.width()) + 3;
+        let Some(column) = items.iter().map(prefix_width).max().map(|width| width + 1) else {
+            return;
+        };
+        let fits = items.iter().all(|item| match item.description {
+            Some((_, Some(width))) => column + width <= self.config.max_width,
+            _ => true,
+        });
+        if !fits {
+            for item in items {
+                docs.insert(item.node.span(), self.convert_split_term_item(ctx, item));
+            }
+            return;
+        }
+
+        for item in items {
+            let padding = column - prefix_width(&item);
+            let term = item.term_text.unwrap_or_default();
+            let mut doc = self.arena.text("/ ") + self.arena.text(term) + self.arena.text(":");
+            if let Some((description, _)) = item.description {
+                // Continued lines are indented to the description column.
+                doc += self.arena.spaces(padding) + description.nest(column as isize);
+            }
+            docs.insert(item.node.span(), doc);
+        }
+    }
+
+    /// Converts a list or enum item, optionally replacing its marker.
+    fn convert_list_item_like(
+        &'a self,
+        ctx: Context,
+        item: &'a SyntaxNode,
+        marker: Option<&str>,
+    ) -> ArenaDoc<'a> {
         let body = self.convert_flow_like(ctx, item, |ctx, child, _| match child.kind() {
             SyntaxKind::ListMarker | SyntaxKind::EnumMarker | SyntaxKind::TermMarker => {
-                FlowItem::spaced(self.arena.text(child.text().as_str()))
+                FlowItem::spaced(match marker {
+                    Some(marker) => self.arena.text(marker.to_string()),
+                    None => self.arena.text(child.text().as_str()),
+                })
             }
             SyntaxKind::Space if child.text().has_linebreak() => {
                 FlowItem::tight(self.arena.hardline())
@@ -198,7 +438,21 @@ impl<'a> PrettyPrinter<'a> {
             }
         }
 
-        let repr = collect_markup_repr(markup);
+        let mut repr = collect_markup_repr(markup);
+        if self.config.enum_marker_style != EnumMarkerStyle::Keep
+            && !self.attr_store.has_enum_numbering()
+        {
+            self.convert_renumbered_enum_items(ctx, markup, &mut repr.item_docs);
+        }
+        if self.config.align_term_descriptions {
+            self.convert_aligned_term_items(ctx, markup, &mut repr.item_docs);
+        }
+        if scope == MarkupScope::Document && self.config.blank_lines_around_blocks > 0 {
+            repr.ensure_blank_lines_around_blocks(self.config.blank_lines_around_blocks);
+        }
+        if scope == MarkupScope::Document && self.items.borrow().is_some() {
+            self.convert_cached_items(ctx, &mut repr);
+        }
         let body = if self.config.wrap_text && scope != MarkupScope::InlineItem {
             self.convert_markup_body_reflow(ctx, &repr)
         } else {
@@ -283,7 +537,7 @@ impl<'a> PrettyPrinter<'a> {
                     } else {
                         ctx
                     };
-                    self.convert_expr(ctx, expr)
+                    self.convert_markup_expr(ctx, expr, repr)
                 } else if is_comment_node(node) {
                     self.convert_comment(ctx, node)
                 } else {
@@ -298,6 +552,150 @@ impl<'a> PrettyPrinter<'a> {
         doc
     }
 
+    fn convert_markup_expr(
+        &'a self,
+        ctx: Context,
+        expr: Expr<'a>,
+        repr: &MarkupRepr<'a>,
+    ) -> ArenaDoc<'a> {
+        match repr.item_docs.get(&expr.to_untyped().span()) {
+            Some(doc) => doc.clone(),
+            None => self.convert_expr(ctx, expr),
+        }
+    }
+
+    /// Converts top-level items on lines of their own through the cache of formatted items.
+    ///
+    /// Such an item starts at the beginning of a line and is followed by a linebreak,
+    /// so it is laid out the same when printed alone.
+    fn convert_cached_items(&'a self, ctx: Context, repr: &mut MarkupRepr<'a>) {
+        let num_lines = repr.lines.len();
+        for (i, line) in repr.lines.iter().enumerate() {
+            let is_last = i + 1 == num_lines;
+            if line.mixed_text
+                || line.breaks == 0 && !(is_last && repr.end_bound == Boundary::Break)
+            {
+                continue;
+            }
+            let (prefix, node) = match line.nodes.as_slice() {
+                [hash, node] if hash.kind() == SyntaxKind::Hash => ("#", *node),
+                [node] => ("", *node),
+                _ => continue,
+            };
+            let Some(expr) = node.cast::<Expr>() else {
+                continue;
+            };
+            // Items converted in advance and nodes kept as is depend on their siblings.
+            if repr.item_docs.contains_key(&node.span()) || self.attr_store.is_format_disabled(node)
+            {
+                continue;
+            }
+
+            let cached = self
+                .items
+                .borrow_mut()
+                .as_mut()
+                .and_then(|items| items.take(node));
+            let (text, comment_lines) = match cached {
+                Some(cached) => cached,
+                None => {
+                    let doc = self.arena.text(prefix) + self.convert_expr(ctx, expr);
+                    // Lines may be indented with tabs, which are kept in the text.
+                    let mut writer = OutputWriter::new(&self.config, true);
+                    if doc.render_raw(self.config.max_width, &mut writer).is_err() {
+                        continue;
+                    }
+                    let (mut text, comment_lines) = writer.finish();
+                    (text.split_off(prefix.len()), comment_lines)
+                }
+            };
+            let doc = self.arena.intersperse(
+                text.split('\n').enumerate().map(|(i, line)| {
+                    if comment_lines.binary_search(&i).is_ok() {
+                        self.convert_comment_line(line)
+                    } else {
+                        self.arena.text(line.to_string())
+                    }
+                }),
+                self.arena.hardline(),
+            );
+            if let Some(items) = self.items.borrow_mut().as_mut() {
+                items.insert(node, text, comment_lines);
+            }
+            repr.item_docs.insert(node.span(), doc);
+        }
+    }
+
+    /// Converts enum items with markers normalized according to `enum_marker_style`.
+    ///
+    /// Only enums numbered sequentially from 1 are changed. Since Typst may continue an enum
+    /// across code, enums mixed with code or labels are left alone. Documents setting `start`
+    /// or `reversed` of enums are not changed at all, as explicit numbers override them.
+    fn convert_renumbered_enum_items(
+        &'a self,
+        ctx: Context,
+        markup: Markup<'a>,
+        docs: &mut FxHashMap<Span, ArenaDoc<'a>>,
+    ) {
+        let mut runs = vec![];
+        let mut items = vec![];
+        let mut is_plain = true;
+        let mut after_hash = false;
+        for node in markup.to_untyped().children() {
+            match node.kind() {
+                SyntaxKind::EnumItem => {
+                    is_plain &= !self.attr_store.is_format_disabled(node);
+                    items.push(node);
+                }
+                SyntaxKind::Space | SyntaxKind::Parbreak => {}
+                _ if is_comment_node(node) => {}
+                SyntaxKind::Hash | SyntaxKind::Label | SyntaxKind::Semicolon => {
+                    is_plain &= items.is_empty();
+                }
+                _ if after_hash => is_plain &= items.is_empty(),
+                _ => {
+                    // Other content always ends the enum.
+                    runs.push((std::mem::take(&mut items), is_plain));
+                    is_plain = true;
+                }
+            }
+            after_hash = node.kind() == SyntaxKind::Hash;
+        }
+        runs.push((items, is_plain));
+
+        for (items, _) in runs.into_iter().filter(|(_, is_plain)| *is_plain) {
+            self.renumber_enum_items(ctx, &items, docs);
+        }
+    }
+
+    fn renumber_enum_items(
+        &'a self,
+        ctx: Context,
+        items: &[&'a SyntaxNode],
+        docs: &mut FxHashMap<Span, ArenaDoc<'a>>,
+    ) {
+        let is_sequential = items.iter().enumerate().all(|(i, item)| {
+            item.children()
+                .find(|child| child.kind() == SyntaxKind::EnumMarker)
+                .and_then(|marker| marker.text().strip_suffix('.')?.parse::<usize>().ok())
+                .is_none_or(|number| number == i + 1)
+        });
+        if !is_sequential {
+            return;
+        }
+        for (i, &item) in items.iter().enumerate() {
+            let marker = match self.config.enum_marker_style {
+                EnumMarkerStyle::Keep => continue,
+                EnumMarkerStyle::Auto => "+".to_string(),
+                EnumMarkerStyle::Numbered => format!("{}.", i + 1),
+            };
+            docs.insert(
+                item.span(),
+                self.convert_list_item_like(ctx, item, Some(marker.as_str())),
+            );
+        }
+    }
+
     /// With text-wrapping enabled, spaces may turn to linebreaks, and linebreaks may turn to spaces, if safe.
     fn convert_markup_body_reflow(&'a self, ctx: Context, repr: &MarkupRepr<'a>) -> ArenaDoc<'a> {
         /// For NOT space -> soft-line: \
@@ -379,7 +777,7 @@ impl<'a> PrettyPrinter<'a> {
                 } else if let Some(text) = node.cast::<Text>() {
                     self.convert_text_wrapped(text)
                 } else if let Some(expr) = node.cast::<Expr>() {
-                    self.convert_expr(ctx, expr)
+                    self.convert_markup_expr(ctx, expr, repr)
                 } else if is_comment_node(node) {
                     self.convert_comment(ctx, node)
                 } else {
@@ -405,6 +803,16 @@ impl<'a> PrettyPrinter<'a> {
     }
 }
 
+/// A term item rendered in parts for alignment.
+struct SplitTermItem<'a> {
+    node: &'a SyntaxNode,
+    term: ArenaDoc<'a>,
+    /// The rendered term, if it can be aligned, i.e., it is not empty and takes a single line.
+    term_text: Option<String>,
+    /// The description and its width if it takes a single line.
+    description: Option<(ArenaDoc<'a>, Option<usize>)>,
+}
+
 #[derive(Default)]
 struct MarkupLine<'a> {
     nodes: SmallVec<[&'a SyntaxNode; 4]>,
@@ -416,6 +824,135 @@ struct MarkupRepr<'a> {
     lines: Vec<MarkupLine<'a>>,
     start_bound: Boundary,
     end_bound: Boundary,
+    /// Items converted in advance, as their layout depends on sibling items.
+    item_docs: FxHashMap<Span, ArenaDoc<'a>>,
+}
+
+impl MarkupRepr<'_> {
+    /// Ensures at least `blank_lines` blank lines before and after headings, block equations,
+    /// block raws and function definitions, only where a paragraph already ends.
+    ///
+    /// Leading comments are kept attached to the element they precede.
+    fn ensure_blank_lines_around_blocks(&mut self, blank_lines: usize) {
+        let kinds = self.lines.iter().map(LineKind::of).collect_vec();
+        let mut boundaries = vec![];
+        for (i, kind) in kinds.iter().enumerate() {
+            if !matches!(
+                kind,
+                LineKind::Heading | LineKind::Block | LineKind::FuncDef
+            ) {
+                continue;
+            }
+            // Skip leading comments.
+            let mut first = i;
+            while first > 0
+                && kinds[first - 1] == LineKind::Comment
+                && self.lines[first - 1].breaks == 1
+            {
+                first -= 1;
+            }
+            if first > 0 {
+                boundaries.push(first - 1);
+            }
+            if i + 1 < kinds.len() {
+                boundaries.push(i);
+            }
+        }
+        boundaries.retain(|&i| {
+            let breaks = self.lines[i].breaks;
+            breaks <= blank_lines && (breaks > 1 || self.ends_paragraph(&kinds, i))
+        });
+        for i in boundaries {
+            self.lines[i].breaks = blank_lines + 1;
+        }
+    }
+
+    /// Whether a parbreak between line `i` and the next one is already implied,
+    /// so that adding blank lines there does not change the output.
+    ///
+    /// Only headings end paragraphs by themselves. Text right before or after a block equation
+    /// or raw block belongs to the same paragraph, which a parbreak would split.
+    fn ends_paragraph(&self, kinds: &[LineKind], i: usize) -> bool {
+        if kinds[i] == LineKind::Opaque || kinds[i + 1] == LineKind::Opaque {
+            return false;
+        }
+        // Look backward and forward through lines producing no content.
+        let before = (0..=i)
+            .rev()
+            .find(|&j| !kinds[j].is_transparent() || j > 0 && self.lines[j - 1].breaks > 1)
+            .is_none_or(|j| kinds[j] == LineKind::Heading || kinds[j].is_transparent());
+        let after = (i + 1..kinds.len())
+            .find(|&j| !kinds[j].is_transparent() || self.lines[j].breaks > 1)
+            .is_none_or(|j| kinds[j] == LineKind::Heading || kinds[j].is_transparent());
+        before || after
+    }
+}
+
+/// Kinds of markup lines, regarding whether blank lines can be added around.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineKind {
+    /// A heading, which always ends paragraphs.
+    Heading,
+    /// A block equation or block raw, which continues the paragraph around it.
+    Block,
+    /// A function definition, like `#let f(x) = x`.
+    FuncDef,
+    /// Other code producing no content, like `#set`, `#show` or `#import`.
+    Silent,
+    /// Comments only.
+    Comment,
+    /// List-like items, whose tightness may depend on blank lines around.
+    Opaque,
+    /// Anything else, possibly inline content.
+    Inline,
+}
+
+impl LineKind {
+    fn of(line: &MarkupLine) -> Self {
+        let nodes = line
+            .nodes
+            .iter()
+            .filter(|it| it.kind() != SyntaxKind::Space && !is_comment_node(it))
+            .collect::<SmallVec<[_; 4]>>();
+        let nodes = match nodes.as_slice() {
+            [rest @ .., last]
+                if !rest.is_empty()
+                    && matches!(last.kind(), SyntaxKind::Label | SyntaxKind::Semicolon) =>
+            {
+                rest
+            }
+            nodes => nodes,
+        };
+        match nodes {
+            [] => Self::Comment,
+            [first, ..] if is_special_block_elem(first) => Self::Opaque,
+            [elem] if elem.kind() == SyntaxKind::Heading => Self::Heading,
+            [elem] if is_block_elem(elem) => Self::Block,
+            [hash, code] if hash.kind() == SyntaxKind::Hash => {
+                if let Some(binding) = code.cast::<LetBinding>() {
+                    if matches!(binding.kind(), LetBindingKind::Closure(_))
+                        || matches!(binding.init(), Some(Expr::Closure(_)))
+                    {
+                        Self::FuncDef
+                    } else {
+                        Self::Silent
+                    }
+                } else if matches!(
+                    code.kind(),
+                    SyntaxKind::SetRule | SyntaxKind::ShowRule | SyntaxKind::ModuleImport
+                ) {
+                    Self::Silent
+                } else {
+                    Self::Inline
+                }
+            }
+            _ => Self::Inline,
+        }
+    }
+
+    fn is_transparent(self) -> bool {
+        matches!(self, Self::FuncDef | Self::Silent | Self::Comment)
+    }
 }
 
 /// Markup boundary, deciding whether can break.
@@ -457,20 +994,11 @@ impl Boundary {
 // equation if a line contains text, it will be skipped by the formatter
 // to keep the original format.
 fn collect_markup_repr(markup: Markup<'_>) -> MarkupRepr<'_> {
-    /// A subset of "blocky" elements that we cannot safely handle currently.
-    /// By default show rule, these elements seem to have weak spaces on both sides.
-    /// But this behavior can be changed by wrapping them in a box.
-    fn is_special_block_elem(it: &SyntaxNode) -> bool {
-        matches!(
-            it.kind(),
-            SyntaxKind::ListItem | SyntaxKind::EnumItem | SyntaxKind::TermItem
-        )
-    }
-
     let mut repr = MarkupRepr {
         lines: vec![],
         start_bound: Boundary::Nil,
         end_bound: Boundary::Nil,
+        item_docs: Default::default(),
     };
     let mut current_line = MarkupLine::default();
     for node in markup.to_untyped().children() {
@@ -564,6 +1092,21 @@ fn collect_markup_repr(markup: Markup<'_>) -> MarkupRepr<'_> {
     repr
 }
 
+/// A subset of "blocky" elements that we cannot safely handle currently.
+/// By default show rule, these elements seem to have weak spaces on both sides.
+/// But this behavior can be changed by wrapping them in a box.
+fn is_special_block_elem(it: &SyntaxNode) -> bool {
+    matches!(
+        it.kind(),
+        SyntaxKind::ListItem | SyntaxKind::EnumItem | SyntaxKind::TermItem
+    )
+}
+
+/// Elements that always end paragraphs when exclusive to a line.
+fn is_block_elem(it: &SyntaxNode) -> bool {
+    it.kind() == SyntaxKind::Heading || is_block_equation(it) || is_block_raw(it)
+}
+
 fn is_block_equation(it: &SyntaxNode) -> bool {
     it.cast::<Equation>()
         .is_some_and(|equation| equation.block())
@@ -3,7 +3,7 @@ use typst_syntax::{SyntaxKind, SyntaxNode, ast::*};
 use unicode_width::UnicodeWidthStr;
 
 use super::{Context, PrettyPrinter, context::AlignMode, prelude::*};
-use crate::{AttrStore, ext::StrExt};
+use crate::{AttrStore, FallbackReason, ext::StrExt, output::OutputWriter};
 
 impl<'a> PrettyPrinter<'a> {
     /// Attempt to format a math node as an aligned grid if there are align points.
@@ -13,20 +13,51 @@ impl<'a> PrettyPrinter<'a> {
         math: Math<'a>,
     ) -> Option<ArenaDoc<'a>> {
         // Skip if alignment is disabled or no math align points present
-        if ctx.align_mode == AlignMode::Never
-            || !self.attr_store.can_align_in_math(math.to_untyped())
-        {
+        let node = math.to_untyped();
+        if ctx.align_mode == AlignMode::Never || !self.attr_store.has_math_align_point(node) {
             return None;
         }
-        let ctx = ctx.aligned(AlignMode::Outer);
-        let raw_aligned = collect_aligned(math, &self.attr_store);
-        let aligned = self.render_aligned(ctx, raw_aligned.rows)?;
-
-        let doc = self.print_aligned(aligned, raw_aligned.has_trailing_backslash);
-        Some(doc)
+        if !self.attr_store.can_align_in_math(node) {
+            self.explain_fallback(node, FallbackReason::MathAlignMultilineStr);
+            return None;
+        }
+        // Grids nested in cells are converted again when their parents fall back.
+        // Detached spans are shared by nodes, so they cannot identify them.
+        let cached = !node.span().is_detached();
+        #[cfg(test)]
+        let cached = cached && self.cache_aligned_math.get();
+        let key = cached.then_some((node.span(), ctx));
+        let cached = key.and_then(|key| self.aligned_math.borrow().get(&key).cloned());
+        let (res, fallbacks) = cached.unwrap_or_else(|| {
+            let ctx = ctx.aligned(AlignMode::Outer);
+            let raw_aligned = collect_aligned(math, &self.attr_store);
+            let fallback_count = self.fallback_count();
+            let res = self
+                .render_aligned(ctx, raw_aligned.rows)
+                .map(|aligned| self.print_aligned(aligned, raw_aligned.has_trailing_backslash));
+            if res.is_none() {
+                // The cells are converted again without alignment, recording what is used.
+                self.take_fallbacks_since(fallback_count);
+                self.explain_fallback(node, FallbackReason::MathAlignTooWide);
+            }
+            let fallbacks = self.take_fallbacks_since(fallback_count);
+            if let Some(key) = key {
+                self.aligned_math
+                    .borrow_mut()
+                    .insert(key, (res.clone(), fallbacks.clone()));
+            }
+            (res, fallbacks)
+        });
+        // Taken above and recorded again, so that cache hits record them too.
+        self.replay_fallbacks(&fallbacks);
+        res
     }
 
     /// Build aligned rows by measuring each cell and tracking column widths.
+    ///
+    /// Each cell is rendered to text once to be measured, and the text is printed as is
+    /// in the grid. Grids nested in a cell are converted once, but their text is rendered
+    /// again as part of the cell at each level of nesting.
     fn render_aligned(
         &'a self,
         ctx: Context,
@@ -59,10 +90,10 @@ impl<'a> PrettyPrinter<'a> {
                             .last()
                             .is_some_and(|n| n.kind() == SyntaxKind::LineComment);
 
-                        let mut buf = String::new();
-                        self.convert_math_children(ctx, cell_nodes.into_iter())
-                            .render_fmt(self.config.max_width, &mut buf)
-                            .ok()?;
+                        let doc = self.convert_math_children(ctx, cell_nodes.into_iter());
+                        let mut writer = OutputWriter::new(&self.config, true).with_spaces();
+                        doc.render_raw(self.config.max_width, &mut writer).ok()?;
+                        let (mut buf, comment_lines) = writer.finish();
                         if ends_with_line_comment {
                             buf.push_str("\n "); // ensure an extra line is added
                         }
@@ -80,8 +111,11 @@ impl<'a> PrettyPrinter<'a> {
                             Cell::Empty
                         } else if buf.has_linebreak() {
                             Cell::MultiLine(
-                                buf.lines()
-                                    .map(|line| (line.to_string(), measure_width(line)))
+                                (buf.lines().enumerate())
+                                    .map(|(i, line)| {
+                                        let is_comment = comment_lines.binary_search(&i).is_ok();
+                                        (line.to_string(), measure_width(line), is_comment)
+                                    })
                                     .collect(),
                             )
                         } else {
@@ -135,7 +169,7 @@ impl<'a> PrettyPrinter<'a> {
             Row::Comment(cmt) => {
                 // Emit a full‑line comment followed by a hard linebreak
                 // NOTE: this should not be the last row.
-                self.arena.text(cmt) + self.arena.hardline()
+                self.line_comment_mark() + self.arena.text(cmt) + self.arena.hardline()
             }
             Row::Cells(cells) => {
                 let mut row_doc = self.arena.nil();
@@ -185,7 +219,13 @@ impl<'a> PrettyPrinter<'a> {
                             };
                             let doc = self.arena.spaces(padding_left)
                                 + self.arena.intersperse(
-                                    lines.into_iter().map(|(line, _)| line),
+                                    lines.into_iter().map(|(line, _, is_comment)| {
+                                        if is_comment {
+                                            self.convert_comment_line(&line)
+                                        } else {
+                                            self.arena.text(line)
+                                        }
+                                    }),
                                     self.arena.hardline(),
                                 )
                                 + self.arena.spaces(trailing_padding);
@@ -249,8 +289,8 @@ enum Row<'a> {
 #[derive(Debug)]
 enum Cell {
     Empty,
-    SingleLine(String, usize),       // text and its width
-    MultiLine(Vec<(String, usize)>), // lines with their widths
+    SingleLine(String, usize),             // text and its width
+    MultiLine(Vec<(String, usize, bool)>), // lines with their widths, and whether they start with line comments
 }
 
 impl Cell {
@@ -259,7 +299,7 @@ impl Cell {
         match self {
             Cell::Empty => 0,
             Cell::SingleLine(_, width) => *width,
-            Cell::MultiLine(lines) => lines.iter().map(|(_, width)| *width).max().unwrap_or(0),
+            Cell::MultiLine(lines) => lines.iter().map(|(_, width, _)| *width).max().unwrap_or(0),
         }
     }
 
@@ -268,7 +308,7 @@ impl Cell {
         match self {
             Cell::Empty => 0,
             Cell::SingleLine(_, width) => *width,
-            Cell::MultiLine(lines) => lines.last().map(|(_, width)| *width).unwrap_or(0),
+            Cell::MultiLine(lines) => lines.last().map(|(_, width, _)| *width).unwrap_or(0),
         }
     }
 }
@@ -306,25 +346,21 @@ fn collect_aligned<'a>(math: Math<'a>, attrs: &AttrStore) -> RawAligned<'a> {
     }
 
     // Gather all relevant children, then split on linebreaks
-    fn collect_children<'a>(
-        node: &'a SyntaxNode,
-        attrs: &AttrStore,
-        out: &mut Vec<&'a SyntaxNode>,
-    ) {
-        if !(matches!(node.kind(), SyntaxKind::Math | SyntaxKind::MathDelimited)
-            && attrs.has_math_align_point(node))
-        {
-            out.push(node);
-            return;
-        }
-        for child in node.children() {
-            collect_children(child, attrs, out);
-        }
-    }
-
     let flat = {
-        let mut flat = Vec::with_capacity(math.to_untyped().children().len());
-        collect_children(math.to_untyped(), attrs, &mut flat);
+        let math = math.to_untyped();
+        let mut flat = Vec::with_capacity(math.children().len());
+        // An explicit stack is used, as math may be nested arbitrarily deep.
+        let mut stack = vec![math];
+        while let Some(node) = stack.pop() {
+            if matches!(node.kind(), SyntaxKind::Math | SyntaxKind::MathDelimited)
+                && attrs.has_math_align_point(node)
+            {
+                // Children are pushed in reverse, so that they are visited in order.
+                stack.extend(node.children().rev());
+            } else {
+                flat.push(node);
+            }
+        }
         flat
     };
 
@@ -371,3 +407,41 @@ fn collect_aligned<'a>(math: Math<'a>, attrs: &AttrStore) -> RawAligned<'a> {
         has_trailing_backslash,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use typst_syntax::Source;
+
+    use super::*;
+    use crate::{Config, pretty::Mode};
+
+    fn format(text: &str, width: usize, cached: bool) -> String {
+        let source = Source::detached(text);
+        let printer = PrettyPrinter::new(
+            Config::new().with_width(width),
+            AttrStore::new(source.root()),
+        );
+        printer.cache_aligned_math.set(cached);
+        let doc = printer
+            .try_convert_with_mode(source.root(), Mode::Markup)
+            .unwrap();
+        let mut out = String::new();
+        doc.render_fmt(width, &mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_nested_grids_cached() {
+        let text = r#"$
+  g(x) &= cases(
+    cases(1 & "if" x > 1, 0 & "if" x = 1) & "if" x >= 1,
+    -1 & "otherwise",
+  ) + sum_(k = 0)^n a_k x^k \
+  &= "sign"(x)
+$"#;
+        // The outer grids fall back at narrow widths, converting the nested ones again.
+        for width in [0, 20, 40, 80, 120] {
+            assert_eq!(format(text, width, true), format(text, width, false));
+        }
+    }
+}
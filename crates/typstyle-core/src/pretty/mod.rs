@@ -19,17 +19,51 @@ mod table;
 mod text;
 mod util;
 
+use std::cell::{Cell, RefCell};
+
+pub(crate) use comment::format_line_comments;
 pub use context::{Context, Mode};
 use prelude::*;
+use rustc_hash::FxHashMap;
 use style::{FoldStyle, is_multiline_flavored};
-use typst_syntax::{SyntaxNode, ast::*};
+use typst_syntax::{Span, SyntaxNode, ast::*};
+
+use crate::{
+    AttrStore, Budget, Config, Error, FallbackReason, TrailingComma,
+    attr::DocumentAttrs,
+    budget::Deadline,
+    ext::StrExt,
+    incremental::{CachedItem, ItemCache},
+    utils,
+};
+
+/// A node formatted by a fallback path, recorded by its span.
+type SpanFallback = (Span, FallbackReason);
 
-use crate::{AttrStore, Config, Error, ext::StrExt};
+/// The aligned grid of a math node, or `None` if it falls back, with the fallbacks in it.
+type AlignedMath<'a> = (Option<ArenaDoc<'a>>, Vec<SpanFallback>);
 
 pub struct PrettyPrinter<'a> {
     config: Config,
     attr_store: AttrStore,
     arena: Arena<'a>,
+    /// The number of expressions being converted, nested in each other.
+    depth: Cell<usize>,
+    /// When to stop formatting, according to `time_limit_ms`.
+    deadline: Cell<Deadline>,
+    /// Whether the deadline has passed, so that the remaining nodes are kept as is.
+    out_of_time: Cell<bool>,
+    /// The aligned grids of math nodes, so that nested grids are converted only once,
+    /// even when their parents fall back to unaligned output.
+    /// Each is kept with the fallbacks recorded while converting it, to record them again.
+    aligned_math: RefCell<FxHashMap<(Span, Context), AlignedMath<'a>>>,
+    /// Whether `aligned_math` is used, so that tests can compare with uncached output.
+    #[cfg(test)]
+    cache_aligned_math: Cell<bool>,
+    /// The formatted text of top-level items, if kept to format the document again.
+    items: RefCell<Option<ItemCache>>,
+    /// The nodes formatted by fallback paths, if recorded.
+    fallbacks: RefCell<Option<Vec<SpanFallback>>>,
 }
 
 impl<'a> PrettyPrinter<'a> {
@@ -38,6 +72,14 @@ impl<'a> PrettyPrinter<'a> {
             config,
             attr_store,
             arena: Arena::new(),
+            depth: Cell::new(0),
+            deadline: Cell::default(),
+            out_of_time: Cell::new(false),
+            aligned_math: RefCell::default(),
+            #[cfg(test)]
+            cache_aligned_math: Cell::new(true),
+            items: RefCell::default(),
+            fallbacks: RefCell::default(),
         }
     }
 
@@ -72,6 +114,11 @@ impl<'a> PrettyPrinter<'a> {
         doc.nest(self.config.tab_spaces as isize)
     }
 
+    /// Whether trailing commas of broken lists should be omitted.
+    pub(crate) fn omits_trailing_comma(&self) -> bool {
+        self.config.trailing_comma == TrailingComma::Never
+    }
+
     pub(crate) fn block_indent(&'a self, doc: ArenaDoc<'a>) -> ArenaDoc<'a> {
         self.indent(self.arena.line_() + doc) + self.arena.line_()
     }
@@ -80,6 +127,7 @@ impl<'a> PrettyPrinter<'a> {
 impl<'a> PrettyPrinter<'a> {
     fn check_disabled(&'a self, node: &'a SyntaxNode) -> Option<ArenaDoc<'a>> {
         if self.attr_store.is_format_disabled(node) {
+            self.explain_fallback(node, FallbackReason::FormatDisabled);
             Some(self.convert_verbatim_untyped(node))
         } else {
             None
@@ -93,19 +141,121 @@ impl<'a> PrettyPrinter<'a> {
 
     /// For inner or lead nodes.
     fn convert_verbatim_untyped(&'a self, node: &'a SyntaxNode) -> ArenaDoc<'a> {
-        let text = node.clone().into_text();
+        let text = util::verbatim_text(node);
         if !text.has_linebreak() {
-            return self.arena.text(text.to_string());
+            return self.arena.text(text);
         }
         // When the text spans multiple lines, we should split it to ensure proper fitting.
         self.arena
-            .intersperse(
-                node.clone().into_text().lines().map(str::to_string),
-                self.arena.hardline(),
-            )
+            .intersperse(text.lines().map(str::to_string), self.arena.hardline())
             .dedent_to_root()
     }
 
+    /// Converts a node nested in the one being converted.
+    /// Nodes nested deeper than `max_nesting_depth` are kept as is.
+    fn convert_nested(
+        &'a self,
+        node: &'a SyntaxNode,
+        convert: impl FnOnce() -> ArenaDoc<'a>,
+    ) -> ArenaDoc<'a> {
+        let depth = self.depth.get();
+        if depth >= self.config.max_nesting_depth {
+            self.explain_fallback(node, FallbackReason::MaxNestingDepth);
+            return self.convert_verbatim_untyped(node);
+        }
+        if self.is_out_of_time() {
+            return self.convert_verbatim_untyped(node);
+        }
+        self.depth.set(depth + 1);
+        let doc = utils::ensure_stack(convert);
+        self.depth.set(depth);
+        doc
+    }
+
+    /// Checks the size of the input, and starts the clock for `time_limit_ms`.
+    pub(crate) fn start_budget(&self, input_size: usize) -> Result<(), Error> {
+        let max_input_size = self.config.max_input_size;
+        if max_input_size > 0 && input_size > max_input_size {
+            return Err(Error::BudgetExceeded(Budget::InputSize));
+        }
+        self.deadline.set(Deadline::start(&self.config));
+        self.out_of_time.set(false);
+        Ok(())
+    }
+
+    /// Returns the time when formatting should stop.
+    pub(crate) fn deadline(&self) -> Deadline {
+        self.deadline.get()
+    }
+
+    /// Fails if formatting has taken longer than `time_limit_ms`.
+    pub(crate) fn check_time(&self) -> Result<(), Error> {
+        if self.is_out_of_time() {
+            Err(Error::BudgetExceeded(Budget::Time))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns the attributes of the whole document, which cached items depend on.
+    pub(crate) fn document_attrs(&self) -> DocumentAttrs {
+        self.attr_store.document()
+    }
+
+    /// Reuses the text of top-level items formatted before, and keeps that of the new ones.
+    pub(crate) fn use_items(&self, previous: FxHashMap<Span, CachedItem>) {
+        *self.items.borrow_mut() = Some(ItemCache::new(previous));
+    }
+
+    /// Takes the items reused and formatted, if kept.
+    pub(crate) fn take_items(&self) -> Option<ItemCache> {
+        self.items.take()
+    }
+
+    /// Starts recording the nodes formatted by fallback paths.
+    pub(crate) fn record_fallbacks(&self) {
+        *self.fallbacks.borrow_mut() = Some(vec![]);
+        // Cached grids hold the fallbacks recorded while converting them, if any.
+        self.aligned_math.borrow_mut().clear();
+    }
+
+    /// Takes the nodes recorded as formatted by fallback paths.
+    pub(crate) fn take_fallbacks(&self) -> Vec<SpanFallback> {
+        self.fallbacks.take().unwrap_or_default()
+    }
+
+    /// Returns the number of fallbacks recorded so far, to take those after it.
+    fn fallback_count(&self) -> usize {
+        self.fallbacks.borrow().as_ref().map_or(0, Vec::len)
+    }
+
+    /// Takes the fallbacks recorded after `count`.
+    fn take_fallbacks_since(&self, count: usize) -> Vec<SpanFallback> {
+        (self.fallbacks.borrow_mut().as_mut())
+            .map_or_else(Vec::new, |fallbacks| fallbacks.split_off(count))
+    }
+
+    /// Records the given fallbacks again, if recording.
+    fn replay_fallbacks(&self, recorded: &[SpanFallback]) {
+        if let Some(fallbacks) = self.fallbacks.borrow_mut().as_mut() {
+            fallbacks.extend_from_slice(recorded);
+        }
+    }
+
+    /// Records that the node is formatted by a fallback path, if recording.
+    fn explain_fallback(&self, node: &SyntaxNode, reason: FallbackReason) {
+        if let Some(fallbacks) = self.fallbacks.borrow_mut().as_mut() {
+            fallbacks.push((node.span(), reason));
+        }
+    }
+
+    fn is_out_of_time(&self) -> bool {
+        if !self.out_of_time.get() && self.deadline.get().has_passed() {
+            self.out_of_time.set(true);
+        }
+        self.out_of_time.get()
+    }
+
     /// For leaf only.
     fn convert_trivia(&'a self, node: impl AstNode<'a>) -> ArenaDoc<'a> {
         self.convert_trivia_untyped(node.to_untyped())
@@ -121,6 +271,7 @@ impl<'a> PrettyPrinter<'a> {
         node: &'a SyntaxNode,
         mode: Mode,
     ) -> Result<ArenaDoc<'a>, Error> {
+        self.start_budget(node.len())?;
         let ctx = Context::default().with_mode(mode);
         let doc = if let Some(markup) = node.cast() {
             self.convert_markup(ctx, markup)
@@ -133,8 +284,9 @@ impl<'a> PrettyPrinter<'a> {
         } else if let Some(pattern) = node.cast() {
             self.convert_pattern(ctx, pattern)
         } else {
-            return Err(Error::SyntaxError);
+            return Err(Error::SyntaxError(vec![]));
         };
+        self.check_time()?;
         Ok(doc)
     }
 
@@ -142,7 +294,7 @@ impl<'a> PrettyPrinter<'a> {
         if let Some(res) = self.check_disabled(expr.to_untyped()) {
             return res;
         }
-        self.convert_expr_impl(ctx, expr)
+        self.convert_nested(expr.to_untyped(), || self.convert_expr_impl(ctx, expr))
     }
 
     fn convert_expr_impl(&'a self, ctx: Context, expr: Expr<'a>) -> ArenaDoc<'a> {
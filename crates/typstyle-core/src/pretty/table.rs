@@ -2,7 +2,7 @@ use typst_syntax::{SyntaxKind, SyntaxNode, ast::*};
 
 use super::{Context, prelude::*, util::func_name};
 use crate::{
-    PrettyPrinter,
+    FallbackReason, PrettyPrinter,
     ext::StrExt,
     pretty::{Mode, layout::table::TableCollector},
 };
@@ -15,12 +15,18 @@ impl<'a> PrettyPrinter<'a> {
         paren_nodes: &'a [SyntaxNode],
     ) -> Option<ArenaDoc<'a>> {
         // NOTE: args are not empty here
-        let columns = if is_table(table) && is_table_formattable(table, paren_nodes) {
-            get_table_columns(table)
-        } else {
-            None
-        }?;
-        Some(self.convert_table(ctx, paren_nodes, columns))
+        if !is_table(table) {
+            return None;
+        }
+        let columns = check_table_formattable(table, paren_nodes)
+            .and_then(|()| get_table_columns(table).ok_or(FallbackReason::TableUnknownColumns));
+        match columns {
+            Ok(columns) => Some(self.convert_table(ctx, paren_nodes, columns)),
+            Err(reason) => {
+                self.explain_fallback(table.to_untyped(), reason);
+                None
+            }
+        }
     }
 
     /// Handle parenthesized args of a table.
@@ -83,7 +89,10 @@ pub fn is_table(func_call: FuncCall) -> bool {
     matches!(func_name(func_call), Some("table") | Some("grid"))
 }
 
-fn is_table_formattable(func_call: FuncCall, paren_nodes: &[SyntaxNode]) -> bool {
+fn check_table_formattable(
+    func_call: FuncCall,
+    paren_nodes: &[SyntaxNode],
+) -> Result<(), FallbackReason> {
     // 1. no block comments
     if func_call
         .args()
@@ -91,12 +100,16 @@ fn is_table_formattable(func_call: FuncCall, paren_nodes: &[SyntaxNode]) -> bool
         .children()
         .any(|it| matches!(it.kind(), SyntaxKind::BlockComment))
     {
-        return false;
+        return Err(FallbackReason::TableBlockComment);
     }
     // 2. has at least one pos arg
-    paren_nodes
+    if !paren_nodes
         .iter()
         .any(|it| matches!(it.cast::<Arg>(), Some(Arg::Pos(_))))
+    {
+        return Err(FallbackReason::TableNoCells);
+    }
+    Ok(())
 }
 
 fn get_table_columns(func_call: FuncCall) -> Option<usize> {
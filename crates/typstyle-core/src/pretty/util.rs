@@ -36,3 +36,17 @@ pub(super) fn func_name(node: FuncCall<'_>) -> Option<&str> {
         _ => None,
     }
 }
+
+/// Gets the source text of the node, like [`SyntaxNode::into_text`] but without recursion.
+pub(super) fn verbatim_text(node: &SyntaxNode) -> String {
+    let mut text = String::new();
+    let mut stack = vec![node];
+    while let Some(node) = stack.pop() {
+        if node.children().as_slice().is_empty() {
+            text.push_str(node.text());
+        } else {
+            stack.extend(node.children().rev());
+        }
+    }
+    text
+}
@@ -1,3 +1,22 @@
+/// The byte order mark.
+pub const BOM: char = '\u{feff}';
+
+/// Calls `f`, moving to a new stack segment if the current one is about to run out.
+///
+/// Wrap recursive calls over the syntax tree with it, so that deeply nested documents
+/// do not overflow the stack. Where the stack cannot be grown, e.g., in WebAssembly,
+/// `f` is called directly, so the recursion must be bounded as well, like the printer
+/// is by `max_nesting_depth`. Other walks over the tree use explicit stacks.
+#[inline]
+pub(crate) fn ensure_stack<R>(f: impl FnOnce() -> R) -> R {
+    /// The remaining stack size below which a new segment is allocated.
+    const RED_ZONE: usize = 128 * 1024;
+    /// The size of each new stack segment.
+    const STACK_SIZE: usize = 2 * 1024 * 1024;
+
+    stacker::maybe_grow(RED_ZONE, STACK_SIZE, f)
+}
+
 /// Strip trailing whitespace in each line of the input string.
 pub fn strip_trailing_whitespace(s: &str) -> String {
     if s.is_empty() {
@@ -11,7 +30,26 @@ pub fn strip_trailing_whitespace(s: &str) -> String {
     res
 }
 
-pub fn count_spaces_after_last_newline(s: &str, i: usize) -> usize {
+/// Make all line endings in the input string `\r\n`.
+///
+/// Line endings in strings and raw text are converted as well, so that the output
+/// has the same line endings throughout.
+pub fn use_crlf(s: &str) -> String {
+    let mut res = String::with_capacity(s.len() + s.len() / 16);
+    for line in s.split_inclusive('\n') {
+        if let Some(line) = line.strip_suffix('\n') {
+            res.push_str(line.strip_suffix('\r').unwrap_or(line));
+            res.push_str("\r\n");
+        } else {
+            res.push_str(line);
+        }
+    }
+    res
+}
+
+/// Count the columns of the indentation of the line containing position `i`,
+/// where a tab is counted as `tab_width` columns.
+pub fn count_indent_after_last_newline(s: &str, i: usize, tab_width: usize) -> usize {
     // Ensure the byte position `i` is a valid UTF-8 boundary
     debug_assert!(
         s.is_char_boundary(i),
@@ -22,8 +60,15 @@ pub fn count_spaces_after_last_newline(s: &str, i: usize) -> usize {
     if let Some(pos) = s[..i].rfind('\n') {
         // Get the substring after the newline and up to position `i`
         let after_newline = &s[pos + 1..i];
-        // Count the number of consecutive spaces in the substring
-        after_newline.chars().take_while(|&c| c == ' ').count()
+        // Count the columns of consecutive spaces and tabs in the substring
+        after_newline
+            .chars()
+            .map_while(|c| match c {
+                ' ' => Some(1),
+                '\t' => Some(tab_width),
+                _ => None,
+            })
+            .sum()
     } else {
         // If no newline is found, return 0
         0
@@ -106,6 +151,20 @@ mod tests {
         assert_eq!(s, "\n -\n\n");
     }
 
+    #[test]
+    fn test_use_crlf() {
+        assert_eq!(use_crlf(""), "");
+        assert_eq!(use_crlf("a\nb\r\n\nc"), "a\r\nb\r\n\r\nc");
+    }
+
+    #[test]
+    fn test_count_indent_after_last_newline() {
+        let s = "a\n \t  b";
+        assert_eq!(count_indent_after_last_newline(s, 0, 4), 0);
+        assert_eq!(count_indent_after_last_newline(s, s.len(), 4), 7);
+        assert_eq!(count_indent_after_last_newline(s, s.len(), 2), 5);
+    }
+
     #[test]
     fn test_change_indent_basic() {
         let input = "    line1\n        line2\n    line3";
@@ -1,6 +1,6 @@
 use std::{env, fs, path::Path};
 
-use syn::{Attribute, FieldsNamed, Item, ItemStruct, Lit, Meta, Type};
+use syn::{Attribute, FieldsNamed, Item, ItemEnum, ItemStruct, Lit, Meta, Type};
 
 fn main() {
     // Define the path to the Config struct in the typstyle-core crate.
@@ -17,9 +17,16 @@ fn main() {
         .unwrap_or_else(|e| panic!("Failed to parse {}: {}", core_config_path.display(), e));
 
     let config_struct_option = find_config_struct(&ast);
+    let config_enums = find_config_enums(&ast);
+
+    let ts_enum_types: String = config_enums
+        .iter()
+        .copied()
+        .map(generate_ts_type_for_enum)
+        .collect();
 
     let ts_interface_fields = if let Some(config_struct) = config_struct_option {
-        generate_ts_fields_for_config_struct(config_struct)
+        generate_ts_fields_for_config_struct(config_struct, &config_enums)
     } else {
         eprintln!(
             "cargo:warning=Config struct not found in {}. Proceeding with an empty TypeScript interface.",
@@ -28,7 +35,8 @@ fn main() {
         String::new() // Default to empty if Config struct is not found
     };
 
-    let ts_interface = format!("export interface Config {{\n{ts_interface_fields}}}");
+    let ts_interface =
+        format!("{ts_enum_types}export interface Config {{\n{ts_interface_fields}}}");
 
     let out_dir = env::var_os("OUT_DIR")
         .expect("OUT_DIR environment variable not set. This script should be run by Cargo.");
@@ -59,11 +67,62 @@ fn find_config_struct(ast: &syn::File) -> Option<&ItemStruct> {
     None
 }
 
+/// Finds all enum definitions within a parsed Rust file AST.
+/// They are used as the types of `Config` fields.
+fn find_config_enums(ast: &syn::File) -> Vec<&ItemEnum> {
+    ast.items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Enum(item_enum) => Some(item_enum),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Generates a TypeScript union type of string literals from a Rust unit enum.
+/// Variant names are converted to kebab-case, matching `serde(rename_all = "kebab-case")`.
+fn generate_ts_type_for_enum(item_enum: &ItemEnum) -> String {
+    let variants = item_enum
+        .variants
+        .iter()
+        .map(|variant| format!("\"{}\"", to_kebab_case(&variant.ident.to_string())))
+        .collect::<Vec<_>>()
+        .join(" | ");
+    // Doc comments are extracted for fields, so we need to remove their indentation.
+    let doc_comments: String = extract_doc_comments(&item_enum.attrs)
+        .lines()
+        .map(|line| format!("{}\n", line.strip_prefix("    ").unwrap_or(line)))
+        .collect();
+    format!(
+        "{doc_comments}export type {} = {variants};\n\n",
+        item_enum.ident
+    )
+}
+
+/// Converts a PascalCase identifier to kebab-case.
+fn to_kebab_case(ident: &str) -> String {
+    let mut result = String::with_capacity(ident.len() + 4);
+    for (i, c) in ident.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 {
+                result.push('-');
+            }
+            result.push(c.to_ascii_lowercase());
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
 /// Generates TypeScript interface field definitions from a Rust struct's fields.
 /// Includes JSDoc comments extracted from Rust doc comments.
 ///
 /// Assume `Config` is at the top level.
-fn generate_ts_fields_for_config_struct(config_struct: &ItemStruct) -> String {
+fn generate_ts_fields_for_config_struct(
+    config_struct: &ItemStruct,
+    config_enums: &[&ItemEnum],
+) -> String {
     let mut ts_fields_string = String::new();
 
     // Optional: Extract and add doc comments for the interface itself
@@ -74,7 +133,7 @@ fn generate_ts_fields_for_config_struct(config_struct: &ItemStruct) -> String {
         for field in named {
             let field_doc_comments = extract_doc_comments(&field.attrs);
             if let (Some(field_ident), Some(ts_type)) =
-                (&field.ident, rust_type_to_ts_type(&field.ty))
+                (&field.ident, rust_type_to_ts_type(&field.ty, config_enums))
             {
                 ts_fields_string.push_str(&field_doc_comments);
                 ts_fields_string.push_str(&format!("    {field_ident}: {ts_type},\n"));
@@ -90,7 +149,7 @@ fn generate_ts_fields_for_config_struct(config_struct: &ItemStruct) -> String {
 }
 
 /// Converts a Rust type identifier to its corresponding TypeScript type string.
-fn rust_type_to_ts_type(ty: &Type) -> Option<String> {
+fn rust_type_to_ts_type(ty: &Type, config_enums: &[&ItemEnum]) -> Option<String> {
     if let Type::Path(type_path) = ty {
         if type_path.qself.is_none() {
             let last_segment = type_path.path.segments.last()?;
@@ -101,6 +160,12 @@ fn rust_type_to_ts_type(ty: &Type) -> Option<String> {
                 | "f32" | "f64" => Some("number".to_string()),
                 "bool" => Some("boolean".to_string()),
                 "String" => Some("string".to_string()),
+                _ if config_enums
+                    .iter()
+                    .any(|item_enum| item_enum.ident == ident_str) =>
+                {
+                    Some(ident_str)
+                }
                 // Add more mappings if your Config struct uses other types
                 _ => None,
             };
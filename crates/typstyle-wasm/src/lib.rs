@@ -1,7 +1,7 @@
 use std::ops::Range;
 
 use js_sys::Error;
-use typst_syntax::Source;
+use typst_syntax::{Lines, Source};
 use typstyle_core::{Config, Typstyle, format_ast, partial::format_range_ast};
 use wasm_bindgen::prelude::*;
 
@@ -28,6 +28,20 @@ pub fn parse(text: &str) -> Result<String, Error> {
     Ok(format_ast(&root))
 }
 
+/// A syntax error in the content. Errors thrown for erroneous content carry an array of them
+/// in the `diagnostics` property.
+#[wasm_bindgen(getter_with_clone)]
+pub struct SyntaxDiagnostic {
+    /// Start UTF-16 code unit index of the erroneous range
+    pub start: usize,
+    /// End UTF-16 code unit index of the erroneous range
+    pub end: usize,
+    /// The error message
+    pub message: String,
+    /// Hints on how to fix the error
+    pub hints: Vec<String>,
+}
+
 /// Formats the content using the provided configuration.
 #[wasm_bindgen]
 pub fn format(
@@ -36,7 +50,107 @@ pub fn format(
 ) -> Result<String, Error> {
     let config = parse_config(config)?;
     let t = Typstyle::new(config);
-    t.format_text(text).render().map_err(into_error)
+    t.format_text(text)
+        .render()
+        .map_err(|e| into_format_error(text, e))
+}
+
+/// The result of formatting content with cursors.
+#[wasm_bindgen(getter_with_clone)]
+pub struct FormatCursorsResult {
+    /// The formatted text
+    pub text: String,
+    /// UTF-16 code unit indices of the cursors in the formatted text, in the given order
+    pub cursors: Vec<usize>,
+}
+
+/// Formats the content using the provided configuration, and maps the cursors to the formatted text.
+/// The `cursors` are UTF-16 code unit indices, matching JavaScript string indexing.
+///
+/// A cursor inside or next to an unchanged token keeps its position relative to that token,
+/// so editors can restore the cursors after replacing the content.
+#[wasm_bindgen]
+pub fn format_with_cursors(
+    text: &str,
+    cursors: Vec<usize>,
+    #[wasm_bindgen(unchecked_param_type = "Partial<Config>")] config: JsValue,
+) -> Result<FormatCursorsResult, Error> {
+    let config = parse_config(config)?;
+    let t = Typstyle::new(config);
+    let source = Source::detached(text);
+    let cursors = cursors
+        .into_iter()
+        .map(|cursor| utf16_to_byte(&source, cursor))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let result = t
+        .format_text_with_cursors(text, &cursors)
+        .map_err(|e| into_format_error(text, e))?;
+    Ok(FormatCursorsResult {
+        text: result.content,
+        cursors: result
+            .cursors
+            .iter()
+            .map(|cursor| cursor.utf16_offset)
+            .collect(),
+    })
+}
+
+/// A replacement of a range in the original content.
+#[wasm_bindgen(getter_with_clone)]
+pub struct TextEdit {
+    /// Start UTF-16 code unit index of the replaced range
+    pub start: usize,
+    /// End UTF-16 code unit index of the replaced range
+    pub end: usize,
+    /// The text to replace the range with
+    pub text: String,
+}
+
+/// Formats the content using the provided configuration, and returns the changes as minimal edits.
+///
+/// The edits are sorted and do not overlap. Their UTF-16 ranges refer to the original content,
+/// so they should be applied from the last to the first, or all at once by an editor.
+#[wasm_bindgen]
+pub fn format_edits(
+    text: &str,
+    #[wasm_bindgen(unchecked_param_type = "Partial<Config>")] config: JsValue,
+) -> Result<Vec<TextEdit>, Error> {
+    let config = parse_config(config)?;
+    let t = Typstyle::new(config);
+    let edits = t
+        .format_source_edits(Source::detached(text))
+        .map_err(|e| into_format_error(text, e))?;
+    Ok(edits.into_iter().map(into_text_edit).collect())
+}
+
+/// Formats the nodes covering each of the given ranges, and returns the changes as minimal edits.
+/// The `ranges` are flattened pairs of start and end UTF-16 code unit indices.
+///
+/// Ranges whose covering nodes overlap are formatted together. The edits are sorted, do not
+/// overlap, and their UTF-16 ranges refer to the original content.
+#[wasm_bindgen]
+pub fn format_ranges(
+    text: &str,
+    ranges: Vec<usize>,
+    #[wasm_bindgen(unchecked_param_type = "Partial<Config>")] config: JsValue,
+) -> Result<Vec<TextEdit>, Error> {
+    if !ranges.len().is_multiple_of(2) {
+        return Err(Error::new(
+            "Ranges should be pairs of start and end indices",
+        ));
+    }
+    let config = parse_config(config)?;
+    let t = Typstyle::new(config);
+    let source = Source::detached(text);
+    let utf8_ranges = ranges
+        .chunks_exact(2)
+        .map(|pair| to_utf16_range(&source, pair[0], pair[1]))
+        .collect::<Result<Vec<_>, _>>()?;
+    let edits = t
+        .format_source_ranges(source, &utf8_ranges)
+        .map_err(|e| into_format_error(text, e))?;
+    Ok(edits.into_iter().map(into_text_edit).collect())
 }
 
 /// Get the pretty IR for the content.
@@ -47,7 +161,9 @@ pub fn format_ir(
 ) -> Result<String, Error> {
     let config = parse_config(config)?;
     let t = Typstyle::new(config);
-    t.format_text(text).render_ir().map_err(into_error)
+    t.format_text(text)
+        .render_ir()
+        .map_err(|e| into_format_error(text, e))
 }
 
 /// The result of formatting a range within content.
@@ -93,7 +209,7 @@ pub fn format_range(
                 .expect("Invalid end index"),
             text: result.content,
         }),
-        Err(e) => Err(into_error(e)),
+        Err(e) => Err(into_format_error(text, e)),
     }
 }
 
@@ -113,7 +229,7 @@ pub fn format_range_ir(
 
     match t.format_source_range_ir(source, utf8_range) {
         Ok(result) => Ok(result.content),
-        Err(e) => Err(into_error(e)),
+        Err(e) => Err(into_format_error(text, e)),
     }
 }
 
@@ -126,7 +242,15 @@ pub fn get_range_ast(text: &str, start: usize, end: usize) -> Result<String, Err
 
     match format_range_ast(&source, utf8_range) {
         Ok(result) => Ok(result.content),
-        Err(e) => Err(into_error(e)),
+        Err(e) => Err(into_format_error(text, e)),
+    }
+}
+
+fn into_text_edit(edit: typstyle_core::edit::TextEdit) -> TextEdit {
+    TextEdit {
+        start: edit.utf16_range.start,
+        end: edit.utf16_range.end,
+        text: edit.replacement,
     }
 }
 
@@ -147,6 +271,30 @@ fn utf16_to_byte(source: &Source, utf16_idx: usize) -> Result<usize, Error> {
     })
 }
 
+/// Converts a formatting error of the text. Syntax errors are attached to the JavaScript error
+/// as an array of [`SyntaxDiagnostic`] in its `diagnostics` property.
+fn into_format_error(text: &str, err: typstyle_core::Error) -> Error {
+    let error = into_error(&err);
+    if let typstyle_core::Error::SyntaxError(diagnostics) = err {
+        let lines = Lines::new(text.to_string());
+        let to_utf16 = |pos: usize| lines.byte_to_utf16(pos).expect("Invalid index");
+        let diagnostics = diagnostics
+            .into_iter()
+            .map(|diagnostic| {
+                JsValue::from(SyntaxDiagnostic {
+                    start: to_utf16(diagnostic.range.start),
+                    end: to_utf16(diagnostic.range.end),
+                    message: diagnostic.message,
+                    hints: diagnostic.hints,
+                })
+            })
+            .collect::<js_sys::Array>();
+        js_sys::Reflect::set(&error, &JsValue::from_str("diagnostics"), &diagnostics)
+            .expect("Errors should be extensible");
+    }
+    error
+}
+
 fn into_error<E: std::fmt::Display>(err: E) -> Error {
     Error::new(&err.to_string())
 }
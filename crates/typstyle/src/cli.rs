@@ -1,6 +1,6 @@
 use std::{path::PathBuf, sync::LazyLock};
 
-use clap::{Args, CommandFactory, Parser, Subcommand, error::ErrorKind};
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum, error::ErrorKind};
 
 #[derive(Parser)]
 #[command(
@@ -29,6 +29,17 @@ pub struct CliArguments {
     #[arg(long, default_value_t = false, global = true, conflicts_with = "check")]
     pub diff: bool,
 
+    /// The language of the input. By default, files with the `.md` extension are Markdown,
+    /// and other input is Typst. In Markdown, only code blocks fenced with `typ` or `typst`
+    /// are formatted. Directories are searched for `.md` files with `markdown`, and for
+    /// `.typ` files otherwise.
+    #[arg(long, value_enum, global = true)]
+    pub language: Option<Language>,
+
+    /// The mode to parse and format Typst input in.
+    #[arg(long, value_enum, default_value_t, global = true)]
+    pub mode: Mode,
+
     #[command(flatten, next_help_heading = "Format Configuration")]
     pub style: StyleArgs,
 
@@ -87,15 +98,150 @@ pub struct StyleArgs {
     )]
     pub indent_width: usize,
 
+    /// Whether to indent with spaces or tabs. A tab is counted as `indent-width` columns.
+    #[arg(long, value_enum, default_value_t, global = true)]
+    pub indent_style: IndentStyle,
+
+    /// Minimum number of blank lines around headings, block equations, block raws and function
+    /// definitions in top-level markup.
+    #[arg(long, default_value_t = 0, global = true)]
+    pub blank_lines_around_blocks: usize,
+
     /// Disable alphabetical reordering of import items.
     #[arg(long, default_value_t = false, global = true)]
     pub no_reorder_import_items: bool,
 
+    /// The order used to sort import items.
+    #[arg(long, value_enum, default_value_t, global = true)]
+    pub import_sort_order: ImportSortOrder,
+
+    /// When to add trailing commas to arguments, arrays, dictionaries and parameters.
+    #[arg(long, value_enum, default_value_t, global = true)]
+    pub trailing_comma: TrailingComma,
+
+    /// The line ending style of the output.
+    #[arg(long, value_enum, default_value_t, global = true)]
+    pub newline_style: NewlineStyle,
+
+    /// Remove the byte order mark at the start of the input, if any.
+    #[arg(long, default_value_t = false, global = true)]
+    pub strip_bom: bool,
+
+    /// How to write the markers of enum items numbered sequentially from 1.
+    #[arg(long, value_enum, default_value_t, global = true)]
+    pub enum_marker_style: EnumMarkerStyle,
+
+    /// Align the descriptions of consecutive term items in one column.
+    #[arg(long, default_value_t = false, global = true)]
+    pub align_term_descriptions: bool,
+
+    /// Reflow paragraphs in consecutive line comments to fit within the line width.
+    #[arg(long, default_value_t = false, global = true)]
+    pub wrap_comments: bool,
+
+    /// Format Typst code fenced with `example` or `typ` in doc comments.
+    #[arg(long, default_value_t = false, global = true)]
+    pub format_code_in_doc_comments: bool,
+
+    /// Format raw blocks tagged `typ`, `typc` or `typm` as markup, code or math.
+    #[arg(long, default_value_t = false, global = true)]
+    pub format_code_in_raw_blocks: bool,
+
+    /// Format files with syntax errors on a best-effort basis, keeping the erroneous parts as is.
+    #[arg(long, default_value_t = false, global = true)]
+    pub format_erroneous: bool,
+
+    /// Keep expressions nested deeper than this number of levels as is.
+    #[arg(long, default_value_t = 128, global = true)]
+    pub max_nesting_depth: usize,
+
+    /// Skip inputs larger than this number of bytes. `0` means no limit.
+    #[arg(long, default_value_t = 0, global = true)]
+    pub max_input_size: usize,
+
+    /// Skip inputs that take longer than this number of milliseconds to format. `0` means no limit.
+    #[arg(long, default_value_t = 0, global = true)]
+    pub time_limit_ms: u64,
+
     /// Wrap text in markup to fit within the line width, and collapse spaces in markup
     #[arg(long, default_value_t = false, global = true)]
     pub wrap_text: bool,
 }
 
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum IndentStyle {
+    /// Indent with spaces.
+    #[default]
+    Spaces,
+    /// Indent with tabs.
+    Tabs,
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum ImportSortOrder {
+    /// Compare byte by byte, so uppercase letters come first.
+    #[default]
+    Bytewise,
+    /// Compare ignoring case.
+    CaseInsensitive,
+    /// Compare runs of digits by their numeric value.
+    Natural,
+    /// Compare the names bound by the items, i.e., the alias of a renamed item.
+    Alias,
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum TrailingComma {
+    /// Add trailing commas when the list spans multiple lines.
+    #[default]
+    Multiline,
+    /// Never add trailing commas, unless required by the syntax.
+    Never,
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum NewlineStyle {
+    /// Use the line ending of the first line in the input.
+    #[default]
+    Auto,
+    /// Use LF (`\n`).
+    Lf,
+    /// Use CRLF (`\r\n`).
+    Crlf,
+    /// Use CRLF on Windows and LF elsewhere.
+    Native,
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum EnumMarkerStyle {
+    /// Keep markers as written.
+    #[default]
+    Keep,
+    /// Use `+` for all items.
+    Auto,
+    /// Use explicit numbers like `1.` for all items.
+    Numbered,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Language {
+    /// A Typst document.
+    Typst,
+    /// A Markdown document with fenced Typst code blocks.
+    Markdown,
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum Mode {
+    /// Markup, as in a Typst document.
+    #[default]
+    Markup,
+    /// Code, as in a code block.
+    Code,
+    /// Math, as in an equation.
+    Math,
+}
+
 #[derive(Args)]
 pub struct DebugArgs {
     /// Print the AST of the input file
@@ -109,6 +255,10 @@ pub struct DebugArgs {
     /// Show elapsed time taken by the formatter
     #[arg(long, default_value_t = false)]
     pub timing: bool,
+
+    /// Print where typstyle chose not to format to stderr, with reason codes
+    #[arg(long, default_value_t = false)]
+    pub explain: bool,
 }
 
 #[derive(Args)]
@@ -0,0 +1,88 @@
+//! Rendering syntax errors as annotated source snippets.
+
+use colored::Colorize;
+use typst_syntax::Lines;
+use typstyle_core::SyntaxDiagnostic;
+
+/// Syntax errors in a text, displayed with the lines they occur in.
+///
+/// ```text
+///  --> a.typ:1:5
+///   |
+/// 1 | #let
+///   |     ^ expected pattern
+/// ```
+pub struct Snippets<'a> {
+    /// The name of the file or block shown in locations.
+    pub name: &'a str,
+    pub text: &'a str,
+    pub diagnostics: &'a [SyntaxDiagnostic],
+    /// The number of lines before the text in the file.
+    pub line_offset: usize,
+    /// The number of columns the text is indented with in the file.
+    pub column_offset: usize,
+}
+
+impl std::fmt::Display for Snippets<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let lines = Lines::new(self.text.to_string());
+        let position = |pos: usize| {
+            let line = lines.byte_to_line(pos).unwrap_or(0);
+            let column = lines.byte_to_column(pos).unwrap_or(0);
+            (line, column)
+        };
+        let width = self
+            .diagnostics
+            .iter()
+            .map(|diagnostic| position(diagnostic.range.start).0 + self.line_offset + 1)
+            .max()
+            .unwrap_or(1)
+            .to_string()
+            .len();
+        let gutter = format!("{:width$} |", "").blue().bold();
+
+        for (i, diagnostic) in self.diagnostics.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            let (line, column) = position(diagnostic.range.start);
+            let line_range = lines.line_to_range(line).unwrap_or(0..0);
+            let line_text = self.text[line_range.clone()].trim_end_matches(['\n', '\r']);
+            // Only the part of the range on the first line is marked.
+            let marked = self.text
+                [diagnostic.range.start..diagnostic.range.end.min(line_range.end)]
+                .trim_end_matches(['\n', '\r'])
+                .chars()
+                .count()
+                .max(1);
+            let line_no = line + self.line_offset + 1;
+
+            writeln!(
+                f,
+                "{:width$}{} {}:{line_no}:{}",
+                "",
+                "-->".blue().bold(),
+                self.name,
+                column + self.column_offset + 1
+            )?;
+            writeln!(f, "{gutter}")?;
+            writeln!(
+                f,
+                "{} {line_text}",
+                format!("{line_no:>width$} |").blue().bold()
+            )?;
+            write!(
+                f,
+                "{gutter} {:column$}{} {}",
+                "",
+                "^".repeat(marked).yellow().bold(),
+                diagnostic.message.bold()
+            )?;
+            for hint in &diagnostic.hints {
+                write!(f, "\n{:width$} {} {hint}", "", "= hint:".bold())?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -11,15 +11,19 @@ use std::{
 use anyhow::{Context, Result, bail};
 use itertools::Itertools;
 use log::{debug, error, info, warn};
-use typst_syntax::Source;
-use typstyle_core::{Config, Typstyle, format_ast};
+use typst_syntax::Lines;
+use typstyle_core::{Budget, Config, Error, Fallback, SyntaxDiagnostic, Typstyle, format_ast};
 use walkdir::{DirEntry, WalkDir};
 
 use crate::{
     ExitStatus,
-    cli::{CliArguments, DebugArgs, StyleArgs},
+    cli::{
+        CliArguments, DebugArgs, EnumMarkerStyle, ImportSortOrder, IndentStyle, Language, Mode,
+        NewlineStyle, StyleArgs, TrailingComma,
+    },
+    diagnostic::Snippets,
     diff::SourceDiff,
-    fs,
+    fs, markdown,
 };
 
 #[derive(Debug, Clone, Copy)]
@@ -49,13 +53,88 @@ impl StyleArgs {
         Config {
             max_width: self.line_width,
             tab_spaces: self.indent_width,
+            indent_style: self.indent_style.into(),
+            blank_lines_around_blocks: self.blank_lines_around_blocks,
             reorder_import_items: !self.no_reorder_import_items,
+            import_sort_order: self.import_sort_order.into(),
+            trailing_comma: self.trailing_comma.into(),
+            newline_style: self.newline_style.into(),
+            strip_bom: self.strip_bom,
+            enum_marker_style: self.enum_marker_style.into(),
+            align_term_descriptions: self.align_term_descriptions,
+            wrap_comments: self.wrap_comments,
+            format_code_in_doc_comments: self.format_code_in_doc_comments,
+            format_code_in_raw_blocks: self.format_code_in_raw_blocks,
+            format_erroneous: self.format_erroneous,
+            max_nesting_depth: self.max_nesting_depth,
+            max_input_size: self.max_input_size,
+            time_limit_ms: self.time_limit_ms,
             wrap_text: self.wrap_text,
             ..Default::default()
         }
     }
 }
 
+impl From<IndentStyle> for typstyle_core::IndentStyle {
+    fn from(style: IndentStyle) -> Self {
+        match style {
+            IndentStyle::Spaces => Self::Spaces,
+            IndentStyle::Tabs => Self::Tabs,
+        }
+    }
+}
+
+impl From<ImportSortOrder> for typstyle_core::ImportSortOrder {
+    fn from(order: ImportSortOrder) -> Self {
+        match order {
+            ImportSortOrder::Bytewise => Self::Bytewise,
+            ImportSortOrder::CaseInsensitive => Self::CaseInsensitive,
+            ImportSortOrder::Natural => Self::Natural,
+            ImportSortOrder::Alias => Self::Alias,
+        }
+    }
+}
+
+impl From<TrailingComma> for typstyle_core::TrailingComma {
+    fn from(policy: TrailingComma) -> Self {
+        match policy {
+            TrailingComma::Multiline => Self::Multiline,
+            TrailingComma::Never => Self::Never,
+        }
+    }
+}
+
+impl From<EnumMarkerStyle> for typstyle_core::EnumMarkerStyle {
+    fn from(style: EnumMarkerStyle) -> Self {
+        match style {
+            EnumMarkerStyle::Keep => Self::Keep,
+            EnumMarkerStyle::Auto => Self::Auto,
+            EnumMarkerStyle::Numbered => Self::Numbered,
+        }
+    }
+}
+
+impl From<Mode> for typstyle_core::pretty::Mode {
+    fn from(mode: Mode) -> Self {
+        match mode {
+            Mode::Markup => Self::Markup,
+            Mode::Code => Self::Code,
+            Mode::Math => Self::Math,
+        }
+    }
+}
+
+impl From<NewlineStyle> for typstyle_core::NewlineStyle {
+    fn from(style: NewlineStyle) -> Self {
+        match style {
+            NewlineStyle::Auto => Self::Auto,
+            NewlineStyle::Lf => Self::Lf,
+            NewlineStyle::Crlf => Self::Crlf,
+            NewlineStyle::Native => Self::Native,
+        }
+    }
+}
+
 pub fn format_stdin(args: &CliArguments) -> Result<ExitStatus> {
     let typstyle = Typstyle::new(args.style.to_config());
 
@@ -70,14 +149,20 @@ pub fn format(args: &CliArguments) -> Result<ExitStatus> {
     struct Summary {
         format_count: usize,
         unchanged_count: usize,
+        skipped_count: usize,
         error_count: usize,
     }
     let mut summary = Summary::default();
 
     let mode = FormatMode::from_cli(args);
-    let paths = resolve_typst_files(&args.input);
+    // Directories are searched for the files of the language, Typst by default.
+    let (extension, language_name) = match args.language {
+        Some(Language::Markdown) => ("md", "Markdown"),
+        _ => ("typ", "Typst"),
+    };
+    let paths = resolve_files(&args.input, extension);
     if paths.is_empty() {
-        warn!("No Typst files found under the given path(s).");
+        warn!("No {language_name} files found under the given path(s).");
         return Ok(ExitStatus::Success);
     }
 
@@ -88,12 +173,13 @@ pub fn format(args: &CliArguments) -> Result<ExitStatus> {
         let res = format_one(Some(&file), &typstyle, args).unwrap_or_else(|e| {
             error!("{e}");
             summary.error_count += 1;
-            FormatResult::Erroneous
+            FormatResult::Erroneous(vec![])
         });
 
         // Check if the content is already well-formatted (unchanged)
         match res {
             FormatResult::Formatted(_) => summary.format_count += 1,
+            FormatResult::Skipped(_) => summary.skipped_count += 1,
             _ => summary.unchanged_count += 1,
         }
     }
@@ -107,21 +193,27 @@ pub fn format(args: &CliArguments) -> Result<ExitStatus> {
         }
     }
 
+    // Files skipped for exceeding a budget are neither formatted nor checked.
+    let skipped = if summary.skipped_count > 0 {
+        format!(", {} skipped", summary.skipped_count)
+    } else {
+        String::new()
+    };
     match mode {
         FormatMode::Write => debug!(
-            "Successfully formatted {} ({} unchanged) in {:?}",
+            "Successfully formatted {} ({} unchanged{skipped}) in {:?}",
             num_files(summary.format_count),
             summary.unchanged_count,
             duration
         ),
         FormatMode::Check => debug!(
-            "{} would be reformatted ({} already formatted), checked in {:?}",
+            "{} would be reformatted ({} already formatted{skipped}), checked in {:?}",
             num_files(summary.format_count),
             summary.unchanged_count,
             duration
         ),
         FormatMode::Diff => debug!(
-            "{} would be reformatted ({} already formatted), checked with diff in {:?}",
+            "{} would be reformatted ({} already formatted{skipped}), checked with diff in {:?}",
             num_files(summary.format_count),
             summary.unchanged_count,
             duration
@@ -141,13 +233,13 @@ pub fn format(args: &CliArguments) -> Result<ExitStatus> {
     })
 }
 
-/// Formats a single `.typ` file or input from stdin.
+/// Formats a single `.typ` or `.md` file or input from stdin.
 ///
 /// This function formats the file provided as an argument, or reads from stdin if no file is given.
 /// If in-place formatting is requested, it overwrites the file with the formatted content.
 ///
 /// # Parameters
-/// - `input`: An optional path to a `.typ` file to be formatted. If `None`, input is read from stdin.
+/// - `input`: An optional path to a file to be formatted. If `None`, input is read from stdin.
 /// - `args`: CLI arguments.
 ///
 /// # Returns
@@ -162,18 +254,26 @@ fn format_one(
     let use_stdout = !args.inplace && !args.check && !args.diff;
     let unformatted = get_input(input)?;
 
-    let res = format_debug(&unformatted, typstyle, &args.debug);
+    let language = language_of(input, args);
+    let name = input.map_or_else(|| "stdin".to_string(), fs::relativize_path);
+    let res = match language {
+        Language::Typst => format_debug(&unformatted, &name, typstyle, args.mode, &args.debug),
+        Language::Markdown => format_markdown(&unformatted, typstyle, input, args.check),
+    };
     match &res {
         FormatResult::Formatted(res) => {
             if args.inplace {
                 // We have already validated that the input is Some.
                 write_back(input.unwrap(), res)?;
             } else if args.check {
-                if let Some(path) = input {
+                if let Some(path) = input
+                    && language == Language::Typst
+                {
                     info!("Would reformat: {}", fs::relativize_path(path));
                 } else {
                     // For stdin, we don't output anything in check mode
-                    // just rely on the exit code
+                    // just rely on the exit code.
+                    // For Markdown, code blocks are reported by `format_markdown`.
                 }
             } else if args.diff {
                 print_unified_diff(&unformatted, res, input);
@@ -186,18 +286,27 @@ fn format_one(
                 print!("{unformatted}");
             }
         }
-        FormatResult::Erroneous => {
+        FormatResult::Erroneous(diagnostics) => {
             if use_stdout {
                 print!("{unformatted}"); // still prints the original content to enable piping
             }
-            if let Some(path) = input {
-                warn!(
-                    "Failed to parse {}. The source is erroneous.",
-                    fs::relativize_path(path)
-                );
-            } else {
-                warn!("Failed to parse stdin. The source is erroneous.");
+            let snippets = Snippets {
+                name: &name,
+                text: &unformatted,
+                diagnostics,
+                line_offset: 0,
+                column_offset: 0,
+            };
+            warn!(
+                "Failed to parse {name}. The source is erroneous.{}",
+                with_snippets(&snippets)
+            );
+        }
+        FormatResult::Skipped(budget) => {
+            if use_stdout {
+                print!("{unformatted}");
             }
+            warn!("Skipped {name}: {budget} budget exceeded.");
         }
     }
     Ok(res)
@@ -206,26 +315,47 @@ fn format_one(
 enum FormatResult {
     Formatted(String),
     Unchanged,
-    Erroneous,
+    /// The source has syntax errors, which are reported.
+    Erroneous(Vec<SyntaxDiagnostic>),
+    /// Formatting was skipped, as the input exceeds a budget.
+    Skipped(Budget),
 }
 
-fn format_debug(content: &str, typstyle: &Typstyle, args: &DebugArgs) -> FormatResult {
-    let source = Source::detached(content);
-    let root = source.root();
+fn format_debug(
+    content: &str,
+    name: &str,
+    typstyle: &Typstyle,
+    mode: Mode,
+    args: &DebugArgs,
+) -> FormatResult {
     if args.ast {
-        println!("{}", format_ast(root));
+        let root = match mode {
+            Mode::Markup => typst_syntax::parse(content),
+            Mode::Code => typst_syntax::parse_code(content),
+            Mode::Math => typst_syntax::parse_math(content),
+        };
+        println!("{}", format_ast(&root));
     }
 
     let start_time = Instant::now();
-    let f = typstyle.format_source(source);
+    let f = typstyle.format_text_in_mode(content, mode.into());
     if args.pretty_doc {
         match f.render_ir() {
             Ok(ir) => println!("{ir}"),
             Err(e) => error!("Failed to render IR: {e}"),
         }
     }
-    let Ok(res) = f.render() else {
-        return FormatResult::Erroneous;
+    if args.explain {
+        match f.explain() {
+            Ok(fallbacks) => print_fallbacks(content, name, &fallbacks),
+            Err(e) => error!("Failed to explain fallbacks: {e}"),
+        }
+    }
+    let res = match f.render() {
+        Ok(res) => res,
+        Err(Error::SyntaxError(diagnostics)) => return FormatResult::Erroneous(diagnostics),
+        Err(Error::BudgetExceeded(budget)) => return FormatResult::Skipped(budget),
+        Err(_) => return FormatResult::Erroneous(vec![]),
     };
 
     if args.timing {
@@ -240,6 +370,113 @@ fn format_debug(content: &str, typstyle: &Typstyle, args: &DebugArgs) -> FormatR
     }
 }
 
+/// Prints each node formatted by a fallback path as `name:line:column: code: reason`.
+fn print_fallbacks(content: &str, name: &str, fallbacks: &[Fallback]) {
+    let lines = Lines::new(content.to_string());
+    for fallback in fallbacks {
+        let pos = fallback.range.start;
+        let line = lines.byte_to_line(pos).unwrap_or(0) + 1;
+        let column = lines.byte_to_column(pos).unwrap_or(0) + 1;
+        eprintln!(
+            "{name}:{line}:{column}: {}: {}",
+            fallback.reason.code(),
+            fallback.reason
+        );
+    }
+}
+
+/// Formats the Typst code blocks in a Markdown document. Other content is kept as is.
+///
+/// Blocks with syntax errors are kept as is with a warning. If `check` is set, each block that
+/// would be reformatted is reported with the line number of its opening fence.
+fn format_markdown(
+    content: &str,
+    typstyle: &Typstyle,
+    input: Option<&Path>,
+    check: bool,
+) -> FormatResult {
+    let name = input.map_or_else(|| "stdin".to_string(), fs::relativize_path);
+    let mut res = String::with_capacity(content.len());
+    let mut last = 0;
+    for block in markdown::find_typst_blocks(content) {
+        let Some(code) = block.code(content) else {
+            continue;
+        };
+        let config = typstyle.config();
+        let typstyle = Typstyle::new(Config {
+            max_width: config.max_width.saturating_sub(block.indent()),
+            ..config.clone()
+        });
+        let formatted = match typstyle.format_text(&code).render() {
+            Ok(formatted) => formatted,
+            Err(Error::BudgetExceeded(budget)) => {
+                warn!(
+                    "Skipped the code block at {name}:{}: {budget} budget exceeded.",
+                    block.line
+                );
+                continue;
+            }
+            Err(e) => {
+                let diagnostics = match e {
+                    Error::SyntaxError(diagnostics) => diagnostics,
+                    _ => vec![],
+                };
+                let snippets = Snippets {
+                    name: &name,
+                    text: &code,
+                    diagnostics: &diagnostics,
+                    line_offset: block.line,
+                    column_offset: block.indent(),
+                };
+                warn!(
+                    "Failed to parse the code block at {name}:{}. The code is erroneous.{}",
+                    block.line,
+                    with_snippets(&snippets)
+                );
+                continue;
+            }
+        };
+        let Some(formatted) = block.reindent(&formatted) else {
+            continue;
+        };
+        if formatted != content[block.range.clone()] {
+            if check {
+                info!("Would reformat: {name}:{}", block.line);
+            }
+            res.push_str(&content[last..block.range.start]);
+            res.push_str(&formatted);
+            last = block.range.end;
+        }
+    }
+    res.push_str(&content[last..]);
+
+    if res != content {
+        FormatResult::Formatted(res)
+    } else {
+        FormatResult::Unchanged
+    }
+}
+
+/// Renders the snippets on new lines, or nothing if there are no syntax errors.
+fn with_snippets(snippets: &Snippets) -> String {
+    if snippets.diagnostics.is_empty() {
+        String::new()
+    } else {
+        format!("\n{snippets}")
+    }
+}
+
+/// Determines the language of the input from the CLI option or the file extension.
+fn language_of(input: Option<&Path>, args: &CliArguments) -> Language {
+    args.language.unwrap_or_else(|| {
+        if input.is_some_and(|path| path.extension() == Some("md".as_ref())) {
+            Language::Markdown
+        } else {
+            Language::Typst
+        }
+    })
+}
+
 fn get_input(input: Option<&Path>) -> Result<String> {
     match input {
         Some(path) => std::fs::read_to_string(path)
@@ -259,7 +496,8 @@ fn write_back(path: &Path, content: &str) -> Result<()> {
         .with_context(|| format!("failed to write to the file {}", path.display()))
 }
 
-fn resolve_typst_files(input: &[PathBuf]) -> Vec<PathBuf> {
+/// Collects the given files, and the files with the extension under the given directories.
+fn resolve_files(input: &[PathBuf], extension: &str) -> Vec<PathBuf> {
     fn is_hidden(entry: &DirEntry) -> bool {
         entry
             .file_name()
@@ -277,7 +515,9 @@ fn resolve_typst_files(input: &[PathBuf]) -> Vec<PathBuf> {
                 .filter_entry(|e| !is_hidden(e))
                 .filter_map(Result::ok);
             for entry in entries {
-                if entry.file_type().is_file() && entry.path().extension() == Some("typ".as_ref()) {
+                if entry.file_type().is_file()
+                    && entry.path().extension() == Some(extension.as_ref())
+                {
                     files.push(entry.into_path());
                 }
             }
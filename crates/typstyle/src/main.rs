@@ -1,8 +1,10 @@
 mod cli;
+mod diagnostic;
 mod diff;
 mod fmt;
 mod fs;
 mod logging;
+mod markdown;
 
 use std::{io::Write, process::ExitCode};
 
@@ -0,0 +1,135 @@
+//! Locating Typst code blocks fenced in Markdown documents.
+//!
+//! Only fenced code blocks with an indentation of at most 3 spaces are recognized,
+//! as in CommonMark. Blocks in block quotes are not recognized.
+
+use std::ops::Range;
+
+/// A fenced code block tagged `typ` or `typst` in a Markdown document.
+pub struct TypstBlock<'a> {
+    /// The 1-based line number of the opening fence.
+    pub line: usize,
+    /// The byte range of the code between the fences.
+    pub range: Range<usize>,
+    fence: Fence<'a>,
+}
+
+impl TypstBlock<'_> {
+    /// Returns the number of spaces the fences are indented with.
+    pub fn indent(&self) -> usize {
+        self.fence.indent
+    }
+
+    /// Extracts the code of the block, with the indentation of the fence removed.
+    /// Returns `None` if the code is blank.
+    pub fn code(&self, text: &str) -> Option<String> {
+        let code = &text[self.range.clone()];
+        if code.trim().is_empty() {
+            return None;
+        }
+        Some(
+            code.split_inclusive('\n')
+                .map(|line| strip_indent(line, self.fence.indent))
+                .collect(),
+        )
+    }
+
+    /// Indents the formatted code as the fence. Returns `None` if a line of the code
+    /// would close the block.
+    pub fn reindent(&self, formatted: &str) -> Option<String> {
+        let indent = " ".repeat(self.fence.indent);
+        let mut res = String::with_capacity(formatted.len());
+        for line in formatted.split_inclusive('\n') {
+            if line.trim().is_empty() {
+                res.push_str(line.trim_start_matches([' ', '\t']));
+            } else if self.fence.is_closed_by(line) {
+                return None;
+            } else {
+                res.push_str(&indent);
+                res.push_str(line);
+            }
+        }
+        Some(res)
+    }
+}
+
+/// Finds the fenced code blocks tagged `typ` or `typst` in the Markdown text.
+/// Blocks that are not closed are ignored.
+pub fn find_typst_blocks(text: &str) -> Vec<TypstBlock<'_>> {
+    let mut blocks = vec![];
+    let mut open: Option<(usize, Fence<'_>, usize)> = None;
+    let mut offset = 0;
+    for (i, line) in text.split_inclusive('\n').enumerate() {
+        let end = offset + line.len();
+        match open.take() {
+            None => open = parse_fence(line).map(|fence| (i + 1, fence, end)),
+            Some((line_no, fence, start)) => {
+                if !fence.is_closed_by(line) {
+                    open = Some((line_no, fence, start));
+                } else if fence.is_typst() {
+                    blocks.push(TypstBlock {
+                        line: line_no,
+                        range: start..offset,
+                        fence,
+                    });
+                }
+            }
+        }
+        offset = end;
+    }
+    blocks
+}
+
+struct Fence<'a> {
+    /// The number of spaces before the fence.
+    indent: usize,
+    /// A run of at least 3 backticks or tildes.
+    marker: &'a str,
+    /// The info string after the marker, which starts with the language.
+    info: &'a str,
+}
+
+impl Fence<'_> {
+    fn is_typst(&self) -> bool {
+        matches!(self.info.split_whitespace().next(), Some("typ" | "typst"))
+    }
+
+    /// Checks if the line is a closing fence of this fence.
+    fn is_closed_by(&self, line: &str) -> bool {
+        // A closing fence is at least as long as the opening one.
+        parse_fence(line)
+            .is_some_and(|fence| fence.marker.starts_with(self.marker) && fence.info.is_empty())
+    }
+}
+
+fn parse_fence(line: &str) -> Option<Fence<'_>> {
+    let trimmed = line.trim_start_matches(' ');
+    let indent = line.len() - trimmed.len();
+    if indent > 3 {
+        return None;
+    }
+    let ch = trimmed
+        .chars()
+        .next()
+        .filter(|ch| matches!(ch, '`' | '~'))?;
+    let len = trimmed.len() - trimmed.trim_start_matches(ch).len();
+    if len < 3 {
+        return None;
+    }
+    let (marker, info) = trimmed.split_at(len);
+    let info = info.trim();
+    if ch == '`' && info.contains('`') {
+        return None;
+    }
+    Some(Fence {
+        indent,
+        marker,
+        info,
+    })
+}
+
+/// Removes up to `indent` leading spaces from the line.
+fn strip_indent(line: &str, indent: usize) -> &str {
+    let spaces = line.len() - line.trim_start_matches(' ').len();
+    &line[spaces.min(indent)..]
+}
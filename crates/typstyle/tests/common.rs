@@ -11,7 +11,7 @@ use insta_cmd::get_cargo_bin;
 use tempfile::TempDir;
 
 pub const INSTA_FILTERS: &[(&str, &str)] = &[
-    (r"(?m)^(.+) in [\w.]+$", "$1 in [DURATION]"),
+    (r"(?m)^(.+) in [\d.]+\w+$", "$1 in [DURATION]"),
     (
         r"(\b[A-Z]:)?[\\/].*?[\\/]\.typstyle-tests---[^\\/]+[\\/]",
         "[TEMP_PATH]/",
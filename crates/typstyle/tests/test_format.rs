@@ -64,6 +64,10 @@ fn test_one_erroneous() {
     #let
     ----- stderr -----
     warn: Failed to parse a.typ. The source is erroneous.
+     --> a.typ:1:5
+      |
+    1 | #let
+      |     ^ expected pattern
     ");
 
     assert!(space.all_unmodified());
@@ -81,6 +85,10 @@ fn test_one_inplace_erroneous() {
 
     ----- stderr -----
     warn: Failed to parse a.typ. The source is erroneous.
+     --> a.typ:1:5
+      |
+    1 | #let
+      |     ^ expected pattern
     ");
 
     assert!(space.all_unmodified());
@@ -102,6 +110,88 @@ fn test_one_check_quiet() {
     assert!(space.all_unmodified());
 }
 
+#[test]
+fn test_one_inplace_crlf() {
+    let mut space = Workspace::new();
+    space.write_tracked("a.typ", "#let a  =  0\r\n#let b = 1\r\n");
+
+    typstyle_cmd_snapshot!(space.cli().args(["a.typ", "-i"]), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+
+    assert_eq!(space.read_string("a.typ"), "#let a = 0\r\n#let b = 1\r\n");
+}
+
+#[test]
+fn test_one_inplace_newline_style() {
+    let mut space = Workspace::new();
+    space.write_tracked("a.typ", "#let a  =  0\r\n#let b = 1\r\n");
+    space.write_tracked("b.typ", "#let a  =  0\n#let b = 1\n");
+
+    typstyle_cmd_snapshot!(space.cli().args(["a.typ", "-i", "--newline-style=lf"]), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+    typstyle_cmd_snapshot!(space.cli().args(["b.typ", "-i", "--newline-style=crlf"]), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+
+    assert_eq!(space.read_string("a.typ"), "#let a = 0\n#let b = 1\n");
+    assert_eq!(space.read_string("b.typ"), "#let a = 0\r\n#let b = 1\r\n");
+}
+
+#[test]
+fn test_one_check_crlf() {
+    let mut space = Workspace::new();
+    space.write_tracked("a.typ", "#let a = 0\r\n#let b = 1\r\n");
+
+    typstyle_cmd_snapshot!(space.cli().args(["a.typ", "--check"]), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+
+    assert!(space.all_unmodified());
+}
+
+#[test]
+fn test_one_inplace_bom() {
+    let mut space = Workspace::new();
+    space.write_tracked("a.typ", "\u{feff}#let a  =  0\n");
+    space.write_tracked("b.typ", "\u{feff}#let a  =  0\n");
+
+    typstyle_cmd_snapshot!(space.cli().args(["a.typ", "-i"]), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+    typstyle_cmd_snapshot!(space.cli().args(["b.typ", "-i", "--strip-bom"]), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+
+    assert_eq!(space.read_string("a.typ"), "\u{feff}#let a = 0\n");
+    assert_eq!(space.read_string("b.typ"), "#let a = 0\n");
+}
+
 #[test]
 fn test_two_0() {
     let mut space = Workspace::new();
@@ -159,6 +159,10 @@ fn test_all_erroneous() {
 
     ----- stderr -----
     warn: Failed to parse x/y/c.typ. The source is erroneous.
+     --> x/y/c.typ:1:16
+      |
+    1 | #let c  =  2; #
+      |                ^ expected expression
     ");
 
     assert!(space.is_unmodified("a.typ"));
@@ -166,6 +170,25 @@ fn test_all_erroneous() {
     assert!(space.is_unmodified("x/y/c.typ"));
 }
 
+#[test]
+fn test_all_skipped_check() {
+    let space = Workspace::new();
+    space.write("a.typ", "#let a = 0\n");
+    space.write("b.typ", "#let b  =  1");
+    space.write("c.typ", "#let c  =  123456789");
+
+    typstyle_cmd_snapshot!(space.cli().args([".", "--check", "-v", "--max-input-size", "16"]), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    Would reformat: b.typ
+    1 file would be reformatted (1 already formatted, 1 skipped), checked in [DURATION]
+
+    ----- stderr -----
+    warn: Skipped c.typ: input size budget exceeded.
+    ");
+}
+
 #[test]
 fn test_all_erroneous_check() {
     let mut space = Workspace::new();
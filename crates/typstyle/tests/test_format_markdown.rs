@@ -0,0 +1,169 @@
+mod common;
+
+use common::{Workspace, typstyle_cmd_snapshot};
+
+const README: &str = "# Title
+
+Some  text  that is   kept.
+
+```typ
+#let  a  =  0
+```
+
+```rust
+let  a  =  0;
+```
+
+- Item:
+
+  ```typst
+  #f( 1,2 )
+  ```
+";
+
+#[test]
+fn test_markdown() {
+    let mut space = Workspace::new();
+    space.write_tracked("README.md", README);
+
+    typstyle_cmd_snapshot!(space.cli().args(["README.md"]), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    # Title
+
+    Some  text  that is   kept.
+
+    ```typ
+    #let a = 0
+    ```
+
+    ```rust
+    let  a  =  0;
+    ```
+
+    - Item:
+
+      ```typst
+      #f(1, 2)
+      ```
+
+    ----- stderr -----
+    ");
+
+    assert!(space.all_unmodified());
+}
+
+#[test]
+fn test_markdown_check() {
+    let mut space = Workspace::new();
+    space.write_tracked("README.md", README);
+
+    typstyle_cmd_snapshot!(space.cli().args(["README.md", "--check"]), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    Would reformat: README.md:5
+    Would reformat: README.md:15
+
+    ----- stderr -----
+    ");
+
+    assert!(space.all_unmodified());
+}
+
+#[test]
+fn test_markdown_diff() {
+    let mut space = Workspace::new();
+    space.write_tracked("a.md", "Text.\n```typ\n#let  a  =  0\n```\n");
+
+    typstyle_cmd_snapshot!(space.cli().args(["a.md", "--diff"]), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    --- a.md
+    +++ a.md
+    @@ -1,4 +1,4 @@
+     Text.
+     ```typ
+    -#let  a  =  0
+    +#let a = 0
+     ```
+
+
+    ----- stderr -----
+    ");
+
+    assert!(space.all_unmodified());
+}
+
+#[test]
+fn test_markdown_erroneous() {
+    let mut space = Workspace::new();
+    space.write_tracked("a.md", "```typ\n#let\n```\n\n```typ\n#let  a  =  0\n```\n");
+
+    typstyle_cmd_snapshot!(space.cli().args(["a.md", "-i"]), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    warn: Failed to parse the code block at a.md:1. The code is erroneous.
+     --> a.md:2:5
+      |
+    2 | #let
+      |     ^ expected pattern
+    ");
+
+    assert_eq!(
+        space.read_string("a.md"),
+        "```typ\n#let\n```\n\n```typ\n#let a = 0\n```\n"
+    );
+}
+
+#[test]
+fn test_markdown_stdin() {
+    let space = Workspace::new();
+
+    typstyle_cmd_snapshot!(space.cli().args(["--language", "markdown"]).pass_stdin("Text.\n\n````typ\n#let  a  =  0\n````\n"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Text.
+
+    ````typ
+    #let a = 0
+    ````
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn test_markdown_dir() {
+    let mut space = Workspace::new();
+    space.write_tracked("a.md", "```typ\n#let  a  =  0\n```\n");
+    space.write_tracked("x/b.md", "```typ\n#let  b  =  1\n```\n");
+    space.write_tracked("x/c.typ", "#let  c  =  2");
+
+    // Without the language, directories are searched for Typst files only.
+    typstyle_cmd_snapshot!(space.cli().args([".", "--check"]), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    Would reformat: x/c.typ
+
+    ----- stderr -----
+    ");
+    typstyle_cmd_snapshot!(space.cli().args([".", "-i", "--language", "markdown"]), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+
+    assert_eq!(space.read_string("a.md"), "```typ\n#let a = 0\n```\n");
+    assert_eq!(space.read_string("x/b.md"), "```typ\n#let b = 1\n```\n");
+    assert!(space.is_unmodified("x/c.typ"));
+}
@@ -43,6 +43,10 @@ fn test_stdin_erroneous() {
     #
     ----- stderr -----
     warn: Failed to parse stdin. The source is erroneous.
+     --> stdin:1:2
+      |
+    1 | #
+      |  ^ expected expression
     ");
 }
 
@@ -163,3 +167,135 @@ fn test_stdin_debug_ast() {
     ----- stderr -----
     "##);
 }
+
+#[test]
+fn test_stdin_mode_code() {
+    let space = Workspace::new();
+
+    typstyle_cmd_snapshot!(space.cli().args(["--mode", "code"]).pass_stdin("// Items.\nlet  x  = (1+2)\nx"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    // Items.
+    let x = (1 + 2)
+    x
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn test_stdin_mode_math() {
+    let space = Workspace::new();
+
+    typstyle_cmd_snapshot!(space.cli().args(["--mode", "math"]).pass_stdin("a  +  b"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    a + b
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn test_stdin_mode_erroneous() {
+    let space = Workspace::new();
+
+    typstyle_cmd_snapshot!(space.cli().args(["--mode", "code"]).pass_stdin("x }"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    x }
+    ----- stderr -----
+    warn: Failed to parse stdin. The source is erroneous.
+     --> stdin:1:3
+      |
+    1 | x }
+      |   ^ unexpected closing brace
+    ");
+}
+
+#[test]
+fn test_stdin_format_erroneous() {
+    let space = Workspace::new();
+
+    typstyle_cmd_snapshot!(space.cli().arg("--format-erroneous").pass_stdin("#let  a  =  1\n\n#let  b  =  2\n]\n#let  c  =  3\n\n#let  d  =  4\n"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    #let a = 1
+
+    #let  b  =  2
+    ]
+    #let  c  =  3
+
+    #let d = 4
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn test_stdin_max_nesting_depth() {
+    let space = Workspace::new();
+
+    typstyle_cmd_snapshot!(space.cli().args(["--max-nesting-depth", "3"]).pass_stdin("#f( g( h( i( 1 ) ) ) )"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    #f(g(h(i( 1 ))))
+
+    ----- stderr -----
+    ");
+
+    typstyle_cmd_snapshot!(space.cli().pass_stdin("#f( g( h( i( 1 ) ) ) )"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    #f(g(h(i(1))))
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn test_stdin_max_input_size() {
+    let space = Workspace::new();
+
+    typstyle_cmd_snapshot!(space.cli().args(["--max-input-size", "8"]).pass_stdin("#let  a  =  1"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    #let  a  =  1
+    ----- stderr -----
+    warn: Skipped stdin: input size budget exceeded.
+    ");
+
+    typstyle_cmd_snapshot!(space.cli().args(["--max-input-size", "13"]).pass_stdin("#let  a  =  1"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    #let a = 1
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn test_stdin_explain() {
+    let space = Workspace::new();
+
+    typstyle_cmd_snapshot!(space.cli().arg("--explain").pass_stdin("// @typstyle off\n#f( a )\n#table(columns: x, [a])\n"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    // @typstyle off
+    #f( a )
+    #table(columns: x, [a])
+
+    ----- stderr -----
+    stdin:2:2: format-disabled: formatting is disabled by `@typstyle off` or syntax errors nearby, so it is kept as is
+    stdin:3:2: table-unknown-columns: the number of columns is unknown, so the table is not laid out in columns
+    ");
+}
@@ -50,6 +50,181 @@ fn test_reorder_import_items() {
     "#);
 }
 
+#[test]
+fn test_import_sort_order() {
+    let space = Workspace::new();
+
+    let stdin = r#"#import "module.typ": item10, Zeta, item2, alpha, beta as a0"#;
+
+    typstyle_cmd_snapshot!(space.cli().pass_stdin(stdin), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    #import "module.typ": Zeta, alpha, beta as a0, item10, item2
+
+    ----- stderr -----
+    "#);
+    typstyle_cmd_snapshot!(space.cli().args(["--import-sort-order=case-insensitive"]).pass_stdin(stdin), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    #import "module.typ": alpha, beta as a0, item10, item2, Zeta
+
+    ----- stderr -----
+    "#);
+    typstyle_cmd_snapshot!(space.cli().args(["--import-sort-order=natural"]).pass_stdin(stdin), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    #import "module.typ": Zeta, alpha, beta as a0, item2, item10
+
+    ----- stderr -----
+    "#);
+    typstyle_cmd_snapshot!(space.cli().args(["--import-sort-order=alias"]).pass_stdin(stdin), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    #import "module.typ": Zeta, beta as a0, alpha, item10, item2
+
+    ----- stderr -----
+    "#);
+}
+
+#[test]
+fn test_trailing_comma() {
+    let space = Workspace::new();
+
+    let stdin = "#let x = (aaaa, bbbb, cccc)
+#let y = (aaaaaaaaaaaaaaaaaaaa,)";
+
+    typstyle_cmd_snapshot!(space.cli().args(["-c=20"]).pass_stdin(stdin), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    #let x = (
+      aaaa,
+      bbbb,
+      cccc,
+    )
+    #let y = (
+      aaaaaaaaaaaaaaaaaaaa,
+    )
+
+    ----- stderr -----
+    ");
+    typstyle_cmd_snapshot!(space.cli().args(["-c=20", "--trailing-comma=never"]).pass_stdin(stdin), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    #let x = (
+      aaaa,
+      bbbb,
+      cccc
+    )
+    #let y = (
+      aaaaaaaaaaaaaaaaaaaa,
+    )
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn test_blank_lines_around_blocks() {
+    let space = Workspace::new();
+
+    let stdin = "= Heading
+Text.
+#let f(x) = x
+Text.
+$ x $
+- Item
+= Heading";
+
+    typstyle_cmd_snapshot!(space.cli().args(["--blank-lines-around-blocks=1"]).pass_stdin(stdin), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    = Heading
+
+    Text.
+    #let f(x) = x
+    Text.
+    $ x $
+    - Item
+    = Heading
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn test_enum_marker_style() {
+    let space = Workspace::new();
+
+    let stdin = "1. a
++ b
+3. c
+
+Text.
+
+5. d
+6. e";
+
+    typstyle_cmd_snapshot!(space.cli().args(["--enum-marker-style=auto"]).pass_stdin(stdin), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    + a
+    + b
+    + c
+
+    Text.
+
+    5. d
+    6. e
+
+    ----- stderr -----
+    ");
+    typstyle_cmd_snapshot!(space.cli().args(["--enum-marker-style=numbered"]).pass_stdin(stdin), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    1. a
+    2. b
+    3. c
+
+    Text.
+
+    5. d
+    6. e
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn test_align_term_descriptions() {
+    let space = Workspace::new();
+
+    let stdin = "/ A: first
+/ Typst: second
+/ Multi: third
+  continued";
+
+    typstyle_cmd_snapshot!(space.cli().args(["--align-term-descriptions"]).pass_stdin(stdin), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    / A:     first
+    / Typst: second
+    / Multi: third
+             continued
+
+    ----- stderr -----
+    ");
+}
+
 #[test]
 fn test_wrap_text() {
     let space = Workspace::new();
@@ -32,6 +32,40 @@ typstyle file1.typ file2.typ
 typstyle src/ docs/
 ```
 
+Files with syntax errors are kept as is. A warning shows each error with its line, column and message, and hints if any:
+
+```
+warn: Failed to parse a.typ. The source is erroneous.
+ --> a.typ:1:5
+  |
+1 | #let
+  |     ^ expected pattern
+```
+
+=== Markdown
+
+```bash
+# Format Typst code blocks in Markdown files
+typstyle -i README.md
+
+# Format Markdown from stdin
+cat README.md | typstyle --language markdown
+```
+
+Files with the `.md` extension are treated as Markdown, unless `--language` is given. Only code blocks fenced with `typ` or `typst` are formatted, and all other content is kept as written. Blocks with syntax errors are kept as is with a warning. With `--check`, each block that would be reformatted is reported with the line number of its opening fence. Directories are searched for `.typ` files, or for `.md` files with `--language markdown`.
+
+=== Code and Math
+
+```bash
+# Format code, as in a code block
+echo 'let  x = (1,2)' | typstyle --mode code
+
+# Format math, as in an equation
+echo 'a  +  b' | typstyle --mode math
+```
+
+By default, Typst input is parsed as markup. With `--mode code` or `--mode math`, it is parsed as the body of a code block or an equation, which is useful for formatting selections in editors.
+
 = Main Options
 
 == Format Control
@@ -72,15 +106,132 @@ typstyle --line-width 100 file.typ
 # Set indentation width (default: 2)
 typstyle -t 4 file.typ
 typstyle --indent-width 4 file.typ
+
+# Indent with tabs, each counted as 4 columns
+typstyle --indent-style tabs -t 4 file.typ
+```
+
+=== Import Sorting
+
+```bash
+# Disable reordering of import items
+typstyle --no-reorder-import-items file.typ
+
+# Sort import items case-insensitively (default: bytewise)
+typstyle --import-sort-order case-insensitive file.typ
+
+# Sort `item2` before `item10`
+typstyle --import-sort-order natural file.typ
+
+# Sort renamed items by their aliases
+typstyle --import-sort-order alias file.typ
 ```
 
+=== Blank Lines
+
+```bash
+# Ensure a blank line around headings, block equations, block raws and function definitions (default: 0)
+typstyle --blank-lines-around-blocks 1 file.typ
+```
+
+Blank lines are only added in top-level markup, where a paragraph already ends. Lines next to list items and text in the same paragraph are left untouched, including text right before or after a block equation or raw block.
+
+=== Enum Markers
+
+```bash
+# Use `+` for enums numbered sequentially from 1 (default: keep)
+typstyle --enum-marker-style auto file.typ
+
+# Number them explicitly instead
+typstyle --enum-marker-style numbered file.typ
+```
+
+Enums that do not start at 1, skip numbers, or are mixed with code are left alone. Since explicit numbers override `start` and `reversed`, documents with `enum` set rules, or `enum` calls with these arguments, are not changed.
+
+=== Term Lists
+
+```bash
+# Align the descriptions of consecutive term items (default: off)
+typstyle --align-term-descriptions file.typ
+```
+
+Descriptions spanning multiple lines continue under the description column. A run of term items is not aligned if a single-line description would exceed the line width.
+
+=== Trailing Commas
+
+```bash
+# Omit trailing commas in broken lists, unless required (default: multiline)
+typstyle --trailing-comma never file.typ
+```
+
+=== Line Endings
+
+```bash
+# Use CRLF line endings (default: auto, detected from the input),
+# also in multiline strings and raw blocks
+typstyle --newline-style crlf file.typ
+
+# Remove the byte order mark, if any (default: keep it)
+typstyle --strip-bom file.typ
+```
+
+=== Doc Comments
+
+```bash
+# Format Typst code fenced with `example` or `typ` in `///` doc comments
+typstyle --format-code-in-doc-comments file.typ
+```
+
+The code is formatted with the same options, and the line width is reduced by the indentation and the comment prefix. Code with syntax errors is kept as is.
+
+=== Raw Blocks
+
+```bash
+# Format raw blocks tagged `typ`, `typc` or `typm`
+typstyle --format-code-in-raw-blocks file.typ
+```
+
+The code is formatted as markup, code or math respectively, and the line width is reduced by the indentation of the block. A block is kept as is if its code has syntax errors, if its code does not start and end on lines of their own, or if its fence would have to change.
+
+=== Syntax Errors
+
+```bash
+# Format the parts of a file without syntax errors
+typstyle --format-erroneous file.typ
+```
+
+Top-level nodes with syntax errors are kept as is, together with the nodes right before and after them, as an error can change how its neighbors are parsed. Content blocks whose brackets are closed are handled the same way inside, so that errors in them do not keep the whole block. All other nodes are formatted as usual. This only applies to markup, so `--mode code` and `--mode math` still reject erroneous input.
+
+=== Nesting Depth
+
+```bash
+# Format expressions nested up to 256 levels deep
+typstyle --max-nesting-depth 256 file.typ
+```
+
+Expressions nested deeper than `--max-nesting-depth` levels, as in generated documents, are kept as is. The default is 128.
+
+=== Budgets
+
+```bash
+# Skip files larger than 1 MB or taking longer than 2 seconds to format
+typstyle --max-input-size 1000000 --time-limit-ms 2000 -i src/
+```
+
+Skipped inputs are kept as is, with a warning such as `warn: Skipped a.typ: time budget exceeded.` The time is checked between syntax nodes, so formatting may run slightly past the limit before it stops. For Markdown files, the limits apply to each code block. Both limits are off by default.
+
 === Text Wrapping
 
 ```bash
 # Wrap text in markup to fit line width
 typstyle --wrap-text file.typ
+
+# Reflow paragraphs in line comments to fit line width
+typstyle --wrap-comments file.typ
 ```
 
+With `--wrap-comments`, a paragraph in consecutive `//` or `///` comments is reflowed only when one of its lines is too long. Blank comment lines and list-like lines start new paragraphs, while lines with `@typstyle` directives, URLs, indented code or fenced code are kept as is.
+
 = Debug Options
 
 == AST Output
@@ -106,6 +257,25 @@ typstyle --pretty-doc file.typ
 typstyle --timing file.typ
 ```
 
+== Fallback Explanation
+
+Some nodes are kept as is or laid out more conservatively than usual, such as code with `@typstyle off`, tables whose columns are unknown, and equations too wide to align. To list where this happens, with a reason code for each:
+
+```bash
+typstyle --explain file.typ
+```
+
+The report is printed to stderr. Each line looks like `file.typ:3:2: table-unknown-columns: ...`. The reason codes are:
+
+- `format-disabled`: formatting is disabled by `@typstyle off`, or by syntax errors nearby.
+- `max-nesting-depth`: the node is nested too deeply.
+- `table-block-comment`: the table has block comments in its arguments.
+- `table-no-cells`: the table has no positional arguments as cells.
+- `table-unknown-columns`: the number of columns is not a simple constant, or may be given by spread arguments.
+- `math-align-multiline-str`: the equation has multiline strings or raws, so it is not aligned.
+- `math-align-too-wide`: the aligned equation would exceed the max width.
+- `dot-chain-inline`: the chain of method calls is on a line with text, so it cannot be broken.
+
 = Logging Options
 
 See CLI help.
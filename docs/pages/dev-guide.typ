@@ -41,6 +41,7 @@ cargo binstall shiroa
 - `crates/typstyle-typlugin/` — typst plugin for embedded usage
 - `crates/typstyle-wasm/` — wasm bindings
 - `tests/` — integration tests and fixtures
+- `fuzz/` — fuzz targets (a separate crate for `cargo fuzz`)
 - `docs/` — documentation source (based on shiroa and written in typst)
 - `contrib/typstyle-embedded/` — typstyle as typst package
 - `playground/` — web-based interactive playground
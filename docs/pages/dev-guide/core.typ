@@ -79,6 +79,25 @@ cargo bench --workspace              # Run all benchmarks
 
 View HTML reports at `target/criterion/report/index.html`.
 
+== Fuzzing
+
+Using #link("https://github.com/rust-fuzz/cargo-fuzz")[cargo-fuzz], which requires a nightly toolchain:
+
+```bash
+cargo install cargo-fuzz
+cargo +nightly fuzz list                  # List fuzz targets
+cargo +nightly fuzz run format_text       # Run a target until it fails
+cargo +nightly fuzz run format_range -- -max_total_time=300
+```
+
+The targets check that formatting never panics, and that the output has no syntax errors, keeps the tokens of the input (except for commas and whitespace) and is stable when formatted again:
+
+- `format_text` formats arbitrary text.
+- `format_generated` formats documents generated from the Typst grammar, which are mostly valid.
+- `format_range` formats a range of arbitrary text, and checks the text with the range replaced.
+
+The seed corpus in `fuzz/corpus/` is generated from small unit test fixtures by `scripts/generate-fuzz-corpus.py`. Failing inputs are saved in `fuzz/artifacts/`, and can be reproduced with `cargo +nightly fuzz run <target> <file>`.
+
 = Code Quality
 
 == Formatting and Linting
@@ -41,7 +41,9 @@ Use ```typ // @typstyle off``` or ```typ /* @typstyle off */``` to disable the f
 
 Typstyle automatically preserves original formatting when it encounters issues:
 
-- *Syntax errors*: Code with parsing errors is left unchanged
+- *Syntax errors*: Code with parsing errors is left unchanged, or only its erroneous parts with `--format-erroneous`
+- *Deep nesting*: Expressions nested more than `max_nesting_depth` (128 by default) levels deep, as in generated documents, are kept as is
+- *Budgets*: Inputs exceeding `max_input_size` or `time_limit_ms`, if set, are not formatted at all
 - *Complex edge cases*: Rare constructs that may downgrade formatting capabilities
 
 In these cases, you don't need an escape hatch—typstyle handles it automatically.
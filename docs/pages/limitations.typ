@@ -30,6 +30,8 @@ We fall back to a plain layout (structure preserved) in these cases:
 - It lacks a `columns` argument or uses spread arguments which possibly define columns.
 - The `columns` argument is not a simple constant expression.
 
+Run `typstyle --explain` to list the tables that fall back, with the reason for each.
+
 = Compact Layout
 
 
@@ -0,0 +1,10 @@
+//! Formats documents generated from the Typst grammar.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use typstyle_fuzz::grammar::Document;
+
+fuzz_target!(|doc: Document| {
+    typstyle_fuzz::check_format_text(&doc.render());
+});
@@ -0,0 +1,20 @@
+//! Formats a range of arbitrary text.
+//!
+//! The first 4 bytes of the input are the start and end of the range, as little-endian `u16`s,
+//! and the rest is the text.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Some(([s0, s1, e0, e1], text)) = data.split_first_chunk::<4>() else {
+        return;
+    };
+    let Ok(text) = std::str::from_utf8(text) else {
+        return;
+    };
+    let to_offset = |bytes: [u8; 2]| text.floor_char_boundary(u16::from_le_bytes(bytes) as usize);
+    let (start, end) = (to_offset([*s0, *s1]), to_offset([*e0, *e1]));
+    typstyle_fuzz::check_format_range(text, start.min(end)..start.max(end));
+});
@@ -0,0 +1,9 @@
+//! Formats arbitrary text.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|text: &str| {
+    typstyle_fuzz::check_format_text(text);
+});
@@ -0,0 +1,469 @@
+//! Generation of Typst documents from arbitrary data.
+//!
+//! Arbitrary bytes rarely form valid Typst, so most of them are rejected by the parser.
+//! The documents generated here are mostly valid, which lets the fuzzer explore the layouts
+//! of nested markup, code and math. Spacing is generated as well, to exercise line breaking.
+
+use std::fmt::{Result, Write};
+
+use arbitrary::Arbitrary;
+
+/// Nodes nested deeper than this are rendered as simple ones, to keep documents small.
+const MAX_DEPTH: usize = 8;
+
+const WORDS: &[&str] = &[
+    "a",
+    "b",
+    "x",
+    "y",
+    "foo",
+    "bar",
+    "text",
+    "page",
+    "body",
+    "it",
+    "alpha",
+    "sum",
+    "lorem",
+    "Ipsum",
+    "data-1",
+    "very-long-identifier-name",
+];
+
+/// A markup document.
+#[derive(Debug, Arbitrary)]
+pub struct Document(Vec<Markup>);
+
+impl Document {
+    /// Renders the document as Typst source.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        write_markups(&mut out, &self.0, 0).expect("writing to a string should not fail");
+        out
+    }
+}
+
+/// A word, also used as an identifier.
+#[derive(Debug, Arbitrary)]
+pub struct Word(u8);
+
+impl Word {
+    fn as_str(&self) -> &'static str {
+        WORDS[self.0 as usize % WORDS.len()]
+    }
+}
+
+#[derive(Debug, Arbitrary)]
+pub enum Spacing {
+    None,
+    Space,
+    Spaces(u8),
+    Newline,
+    Parbreak,
+}
+
+impl Spacing {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "",
+            Self::Space => " ",
+            Self::Spaces(n) => &"        "[..(*n as usize % 8) + 1],
+            Self::Newline => "\n",
+            Self::Parbreak => "\n\n",
+        }
+    }
+}
+
+#[derive(Debug, Arbitrary)]
+pub enum Markup {
+    Text(Word),
+    Space(Spacing),
+    Strong(Vec<Markup>),
+    Emph(Vec<Markup>),
+    Heading(u8, Vec<Markup>),
+    ListItem(Vec<Markup>),
+    EnumItem(Option<u8>, Vec<Markup>),
+    TermItem(Vec<Markup>, Vec<Markup>),
+    Raw(Word),
+    RawBlock(Option<Word>, Vec<Word>),
+    Equation(bool, Vec<Math>),
+    Code(Expr),
+    Label(Word),
+    LineComment(Word),
+    BlockComment(Word),
+}
+
+#[derive(Debug, Arbitrary)]
+pub enum Math {
+    Ident(Word),
+    Num(u8),
+    Op(MathOp),
+    Frac(Box<Math>, Box<Math>),
+    Attach(Box<Math>, bool, Box<Math>),
+    Delimited(Vec<Math>),
+    Call(Word, Vec<Math>),
+    AlignPoint,
+    Linebreak,
+    Space,
+}
+
+#[derive(Debug, Arbitrary)]
+pub enum MathOp {
+    Plus,
+    Minus,
+    Eq,
+    Le,
+    Arrow,
+}
+
+#[derive(Debug, Arbitrary)]
+pub enum Expr {
+    Ident(Word),
+    Int(i16),
+    Str(Word),
+    Bool(bool),
+    None,
+    Array(Vec<Expr>, Spacing),
+    Dict(Vec<(Word, Expr)>, Spacing),
+    Call(Word, Vec<Arg>, Option<Vec<Markup>>),
+    Binary(Box<Expr>, BinOp, Box<Expr>),
+    Unary(bool, Box<Expr>),
+    FieldAccess(Box<Expr>, Word),
+    Paren(Box<Expr>),
+    Closure(Vec<Word>, Box<Expr>),
+    Let(Word, Box<Expr>),
+    Set(Word, Vec<Arg>),
+    Show(Option<Word>, Box<Expr>),
+    Import(Word, Vec<Word>),
+    If(Box<Expr>, Vec<Expr>, Option<Vec<Expr>>),
+    For(Word, Box<Expr>, Vec<Expr>),
+    Block(Vec<Expr>),
+    Content(Vec<Markup>),
+}
+
+#[derive(Debug, Arbitrary)]
+pub enum Arg {
+    Pos(Expr),
+    Named(Word, Expr),
+    Spread(Expr),
+}
+
+#[derive(Debug, Arbitrary)]
+pub enum BinOp {
+    Add,
+    Mul,
+    Div,
+    And,
+    Eq,
+    Lt,
+    In,
+}
+
+impl BinOp {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Add => "+",
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::And => "and",
+            Self::Eq => "==",
+            Self::Lt => "<",
+            Self::In => "in",
+        }
+    }
+}
+
+fn write_markups(out: &mut String, markups: &[Markup], depth: usize) -> Result {
+    for markup in markups {
+        write_markup(out, markup, depth)?;
+    }
+    Ok(())
+}
+
+fn write_markup(out: &mut String, markup: &Markup, depth: usize) -> Result {
+    let depth = depth + 1;
+    if depth > MAX_DEPTH {
+        return write!(out, "text");
+    }
+    match markup {
+        Markup::Text(word) => write!(out, "{}", word.as_str()),
+        Markup::Space(spacing) => write!(out, "{}", spacing.as_str()),
+        Markup::Strong(body) => {
+            write!(out, "*")?;
+            write_markups(out, body, depth)?;
+            write!(out, "*")
+        }
+        Markup::Emph(body) => {
+            write!(out, "_")?;
+            write_markups(out, body, depth)?;
+            write!(out, "_")
+        }
+        Markup::Heading(level, body) => {
+            write!(out, "\n{} ", "=".repeat(*level as usize % 3 + 1))?;
+            write_markups(out, body, depth)?;
+            writeln!(out)
+        }
+        Markup::ListItem(body) => {
+            write!(out, "\n- ")?;
+            write_markups(out, body, depth)?;
+            writeln!(out)
+        }
+        Markup::EnumItem(number, body) => {
+            match number {
+                Some(number) => write!(out, "\n{number}. ")?,
+                None => write!(out, "\n+ ")?,
+            }
+            write_markups(out, body, depth)?;
+            writeln!(out)
+        }
+        Markup::TermItem(term, description) => {
+            write!(out, "\n/ ")?;
+            write_markups(out, term, depth)?;
+            write!(out, ": ")?;
+            write_markups(out, description, depth)?;
+            writeln!(out)
+        }
+        Markup::Raw(word) => write!(out, "`{}`", word.as_str()),
+        Markup::RawBlock(lang, lines) => {
+            write!(out, "\n```{}", lang.as_ref().map_or("", Word::as_str))?;
+            for line in lines {
+                write!(out, "\n  {}", line.as_str())?;
+            }
+            writeln!(out, "\n```")
+        }
+        Markup::Equation(block, body) => {
+            let pad = if *block { " " } else { "" };
+            write!(out, "${pad}")?;
+            write_maths(out, body, depth)?;
+            write!(out, "{pad}$")
+        }
+        Markup::Code(expr) => {
+            write!(out, "#")?;
+            write_expr(out, expr, depth)?;
+            // Avoid continuing the expression with the following markup.
+            write!(out, ";")
+        }
+        Markup::Label(word) => write!(out, "<{}>", word.as_str()),
+        Markup::LineComment(word) => writeln!(out, "// {}", word.as_str()),
+        Markup::BlockComment(word) => write!(out, "/* {} */", word.as_str()),
+    }
+}
+
+fn write_maths(out: &mut String, maths: &[Math], depth: usize) -> Result {
+    for math in maths {
+        write_math(out, math, depth)?;
+    }
+    Ok(())
+}
+
+fn write_math(out: &mut String, math: &Math, depth: usize) -> Result {
+    let depth = depth + 1;
+    if depth > MAX_DEPTH {
+        return write!(out, "x");
+    }
+    match math {
+        Math::Ident(word) => write!(out, "{}", word.as_str()),
+        Math::Num(n) => write!(out, "{n}"),
+        Math::Op(op) => write!(
+            out,
+            " {} ",
+            match op {
+                MathOp::Plus => "+",
+                MathOp::Minus => "-",
+                MathOp::Eq => "=",
+                MathOp::Le => "<=",
+                MathOp::Arrow => "->",
+            }
+        ),
+        Math::Frac(num, denom) => {
+            write_math(out, num, depth)?;
+            write!(out, "/")?;
+            write_math(out, denom, depth)
+        }
+        Math::Attach(base, top, attachment) => {
+            write_math(out, base, depth)?;
+            write!(out, "{}(", if *top { "^" } else { "_" })?;
+            write_math(out, attachment, depth)?;
+            write!(out, ")")
+        }
+        Math::Delimited(body) => {
+            write!(out, "(")?;
+            write_maths(out, body, depth)?;
+            write!(out, ")")
+        }
+        Math::Call(func, args) => {
+            write!(out, "{}(", func.as_str())?;
+            for (i, arg) in args.iter().enumerate() {
+                if i > 0 {
+                    write!(out, ", ")?;
+                }
+                write_math(out, arg, depth)?;
+            }
+            write!(out, ")")
+        }
+        Math::AlignPoint => write!(out, " & "),
+        Math::Linebreak => writeln!(out, " \\"),
+        Math::Space => write!(out, " "),
+    }
+}
+
+fn write_exprs(out: &mut String, exprs: &[Expr], depth: usize) -> Result {
+    for expr in exprs {
+        writeln!(out)?;
+        write_expr(out, expr, depth)?;
+    }
+    writeln!(out)
+}
+
+fn write_expr(out: &mut String, expr: &Expr, depth: usize) -> Result {
+    let depth = depth + 1;
+    if depth > MAX_DEPTH {
+        return write!(out, "none");
+    }
+    match expr {
+        Expr::Ident(word) => write!(out, "{}", word.as_str()),
+        Expr::Int(n) => write!(out, "{}", n.unsigned_abs()),
+        Expr::Str(word) => write!(out, "{:?}", word.as_str()),
+        Expr::Bool(b) => write!(out, "{b}"),
+        Expr::None => write!(out, "none"),
+        Expr::Array(items, spacing) => {
+            write!(out, "(")?;
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    write!(out, ",{}", spacing.as_str())?;
+                }
+                write_expr(out, item, depth)?;
+            }
+            if items.len() == 1 {
+                write!(out, ",")?;
+            }
+            write!(out, ")")
+        }
+        Expr::Dict(pairs, spacing) => {
+            if pairs.is_empty() {
+                return write!(out, "(:)");
+            }
+            write!(out, "(")?;
+            for (i, (key, value)) in pairs.iter().enumerate() {
+                if i > 0 {
+                    write!(out, ",{}", spacing.as_str())?;
+                }
+                write!(out, "{}: ", key.as_str())?;
+                write_expr(out, value, depth)?;
+            }
+            write!(out, ")")
+        }
+        Expr::Call(func, args, content) => {
+            write!(out, "{}", func.as_str())?;
+            write_args(out, args, depth)?;
+            if let Some(content) = content {
+                write!(out, "[")?;
+                write_markups(out, content, depth)?;
+                write!(out, "]")?;
+            }
+            Ok(())
+        }
+        Expr::Binary(lhs, op, rhs) => {
+            write_expr(out, lhs, depth)?;
+            write!(out, " {} ", op.as_str())?;
+            write_expr(out, rhs, depth)
+        }
+        Expr::Unary(not, operand) => {
+            write!(out, "{}", if *not { "not " } else { "-" })?;
+            write_expr(out, operand, depth)
+        }
+        Expr::FieldAccess(target, field) => {
+            write_expr(out, target, depth)?;
+            write!(out, ".{}", field.as_str())
+        }
+        Expr::Paren(inner) => {
+            write!(out, "(")?;
+            write_expr(out, inner, depth)?;
+            write!(out, ")")
+        }
+        Expr::Closure(params, body) => {
+            write!(out, "(")?;
+            for (i, param) in params.iter().enumerate() {
+                if i > 0 {
+                    write!(out, ", ")?;
+                }
+                write!(out, "{}", param.as_str())?;
+            }
+            write!(out, ") => ")?;
+            write_expr(out, body, depth)
+        }
+        Expr::Let(name, init) => {
+            write!(out, "let {} = ", name.as_str())?;
+            write_expr(out, init, depth)
+        }
+        Expr::Set(func, args) => {
+            write!(out, "set {}", func.as_str())?;
+            write_args(out, args, depth)
+        }
+        Expr::Show(selector, transform) => {
+            match selector {
+                Some(selector) => write!(out, "show {}: ", selector.as_str())?,
+                None => write!(out, "show: ")?,
+            }
+            write_expr(out, transform, depth)
+        }
+        Expr::Import(module, items) => {
+            write!(out, "import \"{}.typ\"", module.as_str())?;
+            for (i, item) in items.iter().enumerate() {
+                write!(out, "{} {}", if i == 0 { ":" } else { "," }, item.as_str())?;
+            }
+            Ok(())
+        }
+        Expr::If(condition, then, otherwise) => {
+            write!(out, "if ")?;
+            write_expr(out, condition, depth)?;
+            write!(out, " {{")?;
+            write_exprs(out, then, depth)?;
+            write!(out, "}}")?;
+            if let Some(otherwise) = otherwise {
+                write!(out, " else {{")?;
+                write_exprs(out, otherwise, depth)?;
+                write!(out, "}}")?;
+            }
+            Ok(())
+        }
+        Expr::For(pattern, iterable, body) => {
+            write!(out, "for {} in ", pattern.as_str())?;
+            write_expr(out, iterable, depth)?;
+            write!(out, " {{")?;
+            write_exprs(out, body, depth)?;
+            write!(out, "}}")
+        }
+        Expr::Block(body) => {
+            write!(out, "{{")?;
+            write_exprs(out, body, depth)?;
+            write!(out, "}}")
+        }
+        Expr::Content(body) => {
+            write!(out, "[")?;
+            write_markups(out, body, depth)?;
+            write!(out, "]")
+        }
+    }
+}
+
+fn write_args(out: &mut String, args: &[Arg], depth: usize) -> Result {
+    write!(out, "(")?;
+    for (i, arg) in args.iter().enumerate() {
+        if i > 0 {
+            write!(out, ", ")?;
+        }
+        match arg {
+            Arg::Pos(expr) => write_expr(out, expr, depth)?,
+            Arg::Named(name, expr) => {
+                write!(out, "{}: ", name.as_str())?;
+                write_expr(out, expr, depth)?;
+            }
+            Arg::Spread(expr) => {
+                write!(out, "..")?;
+                write_expr(out, expr, depth)?;
+            }
+        }
+    }
+    write!(out, ")")
+}
@@ -0,0 +1,121 @@
+//! Checks shared by the fuzz targets.
+
+pub mod grammar;
+
+use std::ops::Range;
+
+use typst_syntax::{Source, SyntaxKind, SyntaxNode};
+use typstyle_core::{Config, Typstyle};
+
+/// Returns the formatter used by the fuzz targets.
+///
+/// Import items are not reordered, so that the tokens of the output follow those of the input.
+pub fn typstyle() -> Typstyle {
+    Typstyle::new(Config {
+        reorder_import_items: false,
+        ..Default::default()
+    })
+}
+
+/// Formats the text, and checks that the output keeps the tokens and is stable.
+/// Erroneous text may be rejected, but must not cause a panic.
+pub fn check_format_text(text: &str) {
+    let t = typstyle();
+    let Ok(formatted) = t.format_text(text).render() else {
+        return;
+    };
+    check_formatted(text, &formatted);
+
+    let again = t
+        .format_text(&formatted)
+        .render()
+        .expect("the output should be formattable");
+    assert_eq!(formatted, again, "formatting should be idempotent");
+}
+
+/// Formats the range of the text, and checks that the text with the range replaced keeps the
+/// tokens and is stable. Erroneous text may be rejected, but must not cause a panic.
+pub fn check_format_range(text: &str, range: Range<usize>) {
+    let t = typstyle();
+    let source = Source::detached(text);
+    let erroneous = source.root().erroneous();
+    let Ok(res) = t.format_source_range(source, range) else {
+        return;
+    };
+    let replaced = [
+        &text[..res.source_range.start],
+        &res.content,
+        &text[res.source_range.end..],
+    ]
+    .concat();
+    if erroneous {
+        return;
+    }
+    check_formatted(text, &replaced);
+
+    let formatted_range = res.source_range.start..res.source_range.start + res.content.len();
+    let again = t
+        .format_source_range(Source::detached(&replaced), formatted_range)
+        .expect("the formatted range should be formattable");
+    let replaced_again = [
+        &replaced[..again.source_range.start],
+        &again.content,
+        &replaced[again.source_range.end..],
+    ]
+    .concat();
+    assert_eq!(
+        replaced, replaced_again,
+        "formatting the range should be idempotent"
+    );
+}
+
+/// Checks that the formatted text has no syntax errors and has the same tokens as the original.
+pub fn check_formatted(original: &str, formatted: &str) {
+    let root = typst_syntax::parse(formatted);
+    assert!(
+        !root.erroneous(),
+        "the output should have no syntax errors: {:#?}",
+        root.errors()
+    );
+    assert_eq!(
+        tokens(&typst_syntax::parse(original)),
+        tokens(&root),
+        "formatting should keep the tokens"
+    );
+}
+
+/// Collects the texts of the leaves without whitespace. Commas, semicolons, colons and
+/// parentheses are skipped, as trailing commas, semicolons, redundant parentheses and
+/// the colons of dictionaries like `(: a: 1)` may be added or removed.
+fn tokens(root: &SyntaxNode) -> Vec<String> {
+    fn collect(node: &SyntaxNode, tokens: &mut Vec<String>) {
+        if !node.children().as_slice().is_empty() {
+            for child in node.children() {
+                collect(child, tokens);
+            }
+            return;
+        }
+        if matches!(
+            node.kind(),
+            SyntaxKind::Comma
+                | SyntaxKind::Semicolon
+                | SyntaxKind::Colon
+                | SyntaxKind::LeftParen
+                | SyntaxKind::RightParen
+        ) {
+            return;
+        }
+        let token = node
+            .text()
+            .chars()
+            .filter(|ch| !ch.is_whitespace())
+            .collect::<String>();
+        if !token.is_empty() {
+            tokens.push(token);
+        }
+    }
+
+    let mut tokens = vec![];
+    collect(root, &mut tokens);
+    tokens
+}
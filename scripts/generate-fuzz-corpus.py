@@ -0,0 +1,49 @@
+#!/usr/bin/env python
+"""Generates the seed corpus of the fuzz targets from the small unit test fixtures.
+
+Run from the repository root. The corpus can be minimized further with `cargo fuzz cmin`.
+"""
+
+import hashlib
+import os
+import struct
+
+FIXTURES_DIR = "tests/fixtures/unit"
+CORPUS_DIR = "fuzz/corpus"
+# Larger fixtures slow down fuzzing, and small ones already cover most syntax.
+MAX_SIZE = 300
+
+
+def read_fixtures(directory):
+    for root, dirs, files in os.walk(directory):
+        dirs.sort()
+        for file in sorted(files):
+            if not file.endswith(".typ"):
+                continue
+            with open(os.path.join(root, file), "rb") as f:
+                content = f.read()
+            # Directives only configure the tests.
+            lines = content.splitlines(keepends=True)
+            while lines and lines[0].startswith(b"/// typstyle:"):
+                lines.pop(0)
+            content = b"".join(lines)
+            if content.strip() and len(content) <= MAX_SIZE:
+                yield content
+
+
+def write_seed(target, data):
+    directory = os.path.join(CORPUS_DIR, target)
+    os.makedirs(directory, exist_ok=True)
+    # Name seeds by their hashes, as libFuzzer does.
+    with open(os.path.join(directory, hashlib.sha1(data).hexdigest()), "wb") as f:
+        f.write(data)
+
+
+if __name__ == "__main__":
+    for content in read_fixtures(FIXTURES_DIR):
+        write_seed("format_text", content)
+        # The range covers the middle half of the text.
+        start, end = len(content) // 4, len(content) * 3 // 4
+        write_seed("format_range", struct.pack("<HH", start, end) + content)
+        # The bytes only drive the choices of the grammar, so any data seeds it.
+        write_seed("format_generated", content)
@@ -0,0 +1,33 @@
+/// typstyle: indent_style=tabs tab_spaces=4
+
+#let f(x) = {
+  for i in range(0, 5) {
+       x = x + i
+   }
+  let s = "multiline
+    string"
+  ```typ
+    #raw
+      indented
+  ```
+}
+
+- list item
+  - nested item with a long text that may be wrapped by the formatter when the width is small
+    continued
+
+#figure(
+  table(
+    columns: 2,
+    [a], [b],
+  ),
+  caption: [A table],
+)
+
+/* block comment
+   aligned */
+#let g = (
+  // @typstyle off
+  dict:   (a:1,
+     b: 2),
+)
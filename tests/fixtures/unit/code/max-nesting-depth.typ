@@ -0,0 +1,3 @@
+/// typstyle: max_nesting_depth=2
+
+#let  x  =  (  1  ,  (  2  ,  (  3  ,  (  4  )  )  )  )
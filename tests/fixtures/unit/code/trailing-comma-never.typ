@@ -0,0 +1,18 @@
+/// typstyle: trailing_comma=never
+
+#let single = (1,)
+#let single-long = (aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa,)
+#let array = (aaaaaaaaaaaaaaaa, bbbbbbbbbbbbbbbbbbbb, cccccccccccccccccccc, dddddddddddddddddddd)
+#let dict = (aaaaaaaaaaaa: 1, bbbbbbbbbbbbbbbbbbbb: 2, cccccccccccccccccccc: 3, dddddddddddddddd: 4)
+#let (single-pattern,) = (1,)
+#let (aaaaaaaaaaaaaaaaaaaa, bbbbbbbbbbbbbbbbbbbb, cccccccccccccccccccc, ..dddddddddddd) = array
+#let func(aaaaaaaaaaaaaaaaaaaa, bbbbbbbbbbbbbbbbbbbb, cccccccccccccccccccc: none, ..args) = none
+
+#func(
+  aaaaaaaaaaaaaaaaaaaa,
+  bbbbbbbbbbbbbbbbbbbb, // comment
+  cccccccccccccccccccc: none, /* comment */
+)
+
+#func(aaaaaaaaaaaaaaaaaaaa, bbbbbbbbbbbbbbbbbbbb, (cccccccccccccccccccc, dddddddddddddddddddd))
+#func(aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa)
@@ -0,0 +1,30 @@
+/// typstyle: format_code_in_doc_comments
+
+/// Greets someone.
+///
+/// ```example
+/// #greet(  "world" ,punct:"!")
+/// ```
+///
+/// - name (str): The name.
+/// -> content
+#let greet(name, punct: ".") = [Hello, #name#punct]
+
+#let f(x) = {
+  /// A snippet in an indented doc comment.
+  /// ```typ
+  /// #let items = (1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23,24,25,26)
+  /// ```
+  x
+}
+
+/// A broken snippet is kept as is.
+/// ```example
+/// #greet(
+/// ```
+///
+/// Other languages are kept as is.
+/// ```rust
+/// fn  main() {}
+/// ```
+#let g = none
@@ -0,0 +1,22 @@
+/// typstyle: wrap_comments max_width=60
+
+// This is a long line comment in markup, which should be wrapped to fit within the line width.
+// It continues here.
+//
+// A comment mentioning @typstyle is never wrapped, even if the line is too long for the width.
+// See https://typst.app/docs/reference/syntax/ for more details about the syntax.
+
+#let f(x) = {
+  /// A doc comment inside a code block, which is also long enough to be wrapped.
+  /// - x (int): A parameter with a description which is too long to fit in the line.
+  /// -> int
+  x + 1 // A trailing comment is kept as is, no matter how long it is, since it follows code.
+}
+
+/// ```example
+/// #f(1) // Code in fences is kept as is, as it may be formatted separately later.
+/// ```
+
+// 1. An enum item in a comment, whose continuation lines are indented under its text.
+//    It keeps going on a line indented like the item.
+// + A list item with a word like ```typst #code``` which never starts a wrapped line.
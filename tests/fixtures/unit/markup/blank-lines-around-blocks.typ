@@ -0,0 +1,24 @@
+/// typstyle: blank_lines_around_blocks=1
+
+#import "lib.typ": template
+#show: template
+= Introduction
+Some text before an equation.
+$ a + b = c $
+Some text after it.
+```rust
+fn main() {}
+```
+// A helper function.
+#let f(x) = x + 1
+#let g = (x) => x * 2
+== Functions between text
+Text before.
+#let h(x) = x
+Text after, in the same paragraph.
+- A list item
+= After a list
+#box[
+  = Not in document scope
+  Text.
+]
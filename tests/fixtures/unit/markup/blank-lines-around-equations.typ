@@ -0,0 +1,16 @@
+/// typstyle: blank_lines_around_blocks=1
+
+= Equations
+$ x $
+directly followed by text.
+
+Text before
+$ y $
+and after, in one paragraph.
+
+$ z $
+
+```typ
+#f(x)
+```
+Text after a raw block.
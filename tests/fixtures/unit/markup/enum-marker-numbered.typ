@@ -0,0 +1,22 @@
+/// typstyle: enum_marker_style=numbered
+
+1. First
+2. Second
++ Third
+
+4. Fourth, after a parbreak
+   1. Nested
+   2. Nested
+
+Starting at five:
+5. Five
+6. Six
+
+Skipping a number:
+1. One
+3. Three
+
+Mixed with code:
+1. One
+#let x = 1
+2. Two
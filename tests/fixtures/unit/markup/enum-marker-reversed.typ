@@ -0,0 +1,7 @@
+/// typstyle: enum_marker_style=auto
+
+#enum(reversed: true)[Three][Two][One]
+
+1. Three
+2. Two
+3. One
@@ -0,0 +1,6 @@
+/// typstyle: enum_marker_style=numbered
+
+#set enum(start: 3)
+
++ Three
++ Four
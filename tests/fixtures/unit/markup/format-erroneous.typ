@@ -0,0 +1,26 @@
+/// typstyle: format_erroneous
+
+// A comment before the code.
+#let  a  =  (1,2)  // Trailing comment.
+
+#let  b  =  (3,4)
+]
+/* A block comment after the error. */
+#let  c  =  (5,6)
+
+#let  d  =  (7,8)
+
+#[
+  // Comments in a content block with an error.
+  #let  e  =  (9,10)
+  #let  f  =
+  #let  g  =  (11,12)
+
+  #let  h  =  (13,14)  // Formatted, as the brackets are closed.
+]
+
+#[
+  #let  i  =  (15,16)
+
+  #let  j  =  (  // The bracket is taken by the parentheses.
+]
@@ -0,0 +1,50 @@
+/// typstyle: format_code_in_raw_blocks
+
+Markup:
+
+```typ
+#let  f( x,y )= x+y
+= Heading
+Some   text  with #f(1,2).
+```
+
+Code:
+
+```typc
+let items = (1,2,3)
+for i in items {  i  }
+```
+
+Math:
+
+```typm
+sum_(i=1)^n   i=(n(n+1))/2
+```
+
+- In a list item:
+  ```typ
+  #set text(size:12pt,font:"New Computer Modern",lang:"en",region:"us",fallback:false)
+  ```
+
+With a longer fence:
+
+````typ
+#raw( "```" )
+````
+
+Kept as is:
+
+```typ
+#let f( = 1
+```
+
+```typc
+// A leading comment.
+let x = ( 1,2 )
+```
+
+```typ #let  x = 1 ```
+
+```rust
+fn  main( ) {}
+```
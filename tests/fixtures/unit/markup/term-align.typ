@@ -0,0 +1,16 @@
+/// typstyle: align_term_descriptions
+
+/ A: The first letter.
+/ Typst: A markup-based typesetting system.
+/ Term with *strong*:   Spaces after the colon are normalized.
+/ Multi-line: The description continues
+  on the next line,
+  with a hanging indent.
+/ Empty:
+
+Another glossary:
+/ Long term: #lorem(10)
+/ Short: This run does not fit in the line width, so the descriptions are not aligned at all.
+
+- / Nested: In a list item.
+  / Nested term: Also aligned.
@@ -0,0 +1,21 @@
+// Cases nested in a multi-line derivation
+$
+  f(x) &= cases(
+    x^2 + 2 x + 1 & "if" x >= 0,
+    -x & "otherwise",
+  ) \
+  &= cases(
+    (x + 1)^2 & "if" x >= 0,
+    -x & "otherwise",
+  ) + integral_0^x g(t) dif t + sum_(k = 0)^n a_k x^k \
+  &= h(x)
+$
+
+// Cases nested in cases in a derivation
+$
+  g(x) &= cases(
+    cases(1 & "if" x > 1, 0 & "if" x = 1) & "if" x >= 1,
+    -1 & "otherwise",
+  ) \
+  &= "sign"(x)
+$
@@ -1,6 +1,7 @@
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result, bail};
+use typstyle_core::{EnumMarkerStyle, ImportSortOrder, IndentStyle, NewlineStyle, TrailingComma};
 
 use super::{Options, read_content};
 
@@ -59,10 +60,41 @@ pub fn parse_directives(content: &str) -> Result<Options> {
             "reorder_import_items" | "reorder-import-items" => {
                 config.reorder_import_items = value != Some("false");
             }
+            "import_sort_order" | "import-sort-order" => {
+                config.import_sort_order = match value {
+                    Some("bytewise") => ImportSortOrder::Bytewise,
+                    Some("case-insensitive") => ImportSortOrder::CaseInsensitive,
+                    Some("natural") => ImportSortOrder::Natural,
+                    Some("alias") => ImportSortOrder::Alias,
+                    _ => bail!("Invalid import_sort_order value: {value:?}"),
+                };
+            }
+            "trailing_comma" | "trailing-comma" => {
+                config.trailing_comma = match value {
+                    Some("multiline") => TrailingComma::Multiline,
+                    Some("never") => TrailingComma::Never,
+                    _ => bail!("Invalid trailing_comma value: {value:?}"),
+                };
+            }
             "wrap_text" | "wrap-text" => {
                 config.wrap_text = value != Some("false");
                 config.collapse_markup_spaces |= config.wrap_text;
             }
+            "align_term_descriptions" | "align-term-descriptions" => {
+                config.align_term_descriptions = value != Some("false");
+            }
+            "wrap_comments" | "wrap-comments" => {
+                config.wrap_comments = value != Some("false");
+            }
+            "format_code_in_doc_comments" | "format-code-in-doc-comments" => {
+                config.format_code_in_doc_comments = value != Some("false");
+            }
+            "format_code_in_raw_blocks" | "format-code-in-raw-blocks" => {
+                config.format_code_in_raw_blocks = value != Some("false");
+            }
+            "format_erroneous" | "format-erroneous" => {
+                config.format_erroneous = value != Some("false");
+            }
             "collapse_markup_spaces" | "collapse-markup-spaces" => {
                 config.collapse_markup_spaces = value != Some("false");
             }
@@ -80,6 +112,44 @@ pub fn parse_directives(content: &str) -> Result<Options> {
                         .with_context(|| format!("Invalid tab_spaces value: {v}"))?;
                 }
             }
+            "max_nesting_depth" | "max-nesting-depth" => {
+                if let Some(v) = value {
+                    config.max_nesting_depth = v
+                        .parse()
+                        .with_context(|| format!("Invalid max_nesting_depth value: {v}"))?;
+                }
+            }
+            "blank_lines_around_blocks" | "blank-lines-around-blocks" => {
+                if let Some(v) = value {
+                    config.blank_lines_around_blocks = v
+                        .parse()
+                        .with_context(|| format!("Invalid blank_lines_around_blocks value: {v}"))?;
+                }
+            }
+            "enum_marker_style" | "enum-marker-style" => {
+                config.enum_marker_style = match value {
+                    Some("keep") => EnumMarkerStyle::Keep,
+                    Some("auto") => EnumMarkerStyle::Auto,
+                    Some("numbered") => EnumMarkerStyle::Numbered,
+                    _ => bail!("Invalid enum_marker_style value: {value:?}"),
+                };
+            }
+            "indent_style" | "indent-style" => {
+                config.indent_style = match value {
+                    Some("spaces") => IndentStyle::Spaces,
+                    Some("tabs") => IndentStyle::Tabs,
+                    _ => bail!("Invalid indent_style value: {value:?}"),
+                };
+            }
+            "newline_style" | "newline-style" => {
+                config.newline_style = match value {
+                    Some("auto") => NewlineStyle::Auto,
+                    Some("lf") => NewlineStyle::Lf,
+                    Some("crlf") => NewlineStyle::Crlf,
+                    Some("native") => NewlineStyle::Native,
+                    _ => bail!("Invalid newline_style value: {value:?}"),
+                };
+            }
             _ => bail!("unknown directive: {key}"),
         }
         Ok(())
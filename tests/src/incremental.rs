@@ -0,0 +1,205 @@
+use std::{fs, ops::Range, path::Path};
+
+use libtest_mimic::{Failed, Trial};
+use typst_syntax::Source;
+use typstyle_core::{Config, EnumMarkerStyle, FormatCache, IndentStyle, TrailingComma, Typstyle};
+
+use crate::common::{fixtures_dir, read_source};
+
+/// The number of edits applied to each document.
+const NUM_EDITS: usize = 40;
+
+/// Texts inserted by edits, with and without syntax errors.
+const SNIPPETS: &[&str] = &[
+    " ",
+    "\n",
+    "\n\n",
+    "text ",
+    "#let  x  =  ( 1 ,2 )\n",
+    "#f( a , b )",
+    "= Heading\n",
+    "- item\n",
+    "+ item\n",
+    "1. item\n",
+    "#[\n  1. a\n  2. b\n]\n",
+    "#set enum(start: 3)\n",
+    "// @typstyle off\n",
+    "$ a &= b \\ &= c $\n",
+    "#",
+    "]",
+    "[",
+    "(",
+];
+
+/// The configurations the edits are checked with, named for the tests.
+fn configs() -> Vec<(&'static str, Config)> {
+    vec![
+        ("40char", Config::new().with_width(40)),
+        ("80char", Config::new().with_width(80)),
+        (
+            "enum-auto",
+            Config {
+                enum_marker_style: EnumMarkerStyle::Auto,
+                ..Default::default()
+            },
+        ),
+        (
+            "enum-numbered",
+            Config {
+                enum_marker_style: EnumMarkerStyle::Numbered,
+                ..Default::default()
+            },
+        ),
+        (
+            "trailing-comma-never",
+            Config {
+                trailing_comma: TrailingComma::Never,
+                ..Default::default()
+            },
+        ),
+        (
+            "tabs",
+            Config {
+                indent_style: IndentStyle::Tabs,
+                ..Default::default()
+            },
+        ),
+        (
+            "blank-lines-around-blocks",
+            Config {
+                blank_lines_around_blocks: 1,
+                ..Default::default()
+            },
+        ),
+    ]
+}
+
+/// Creates a property test for each package and configuration, checking that formatting
+/// after random edits with the cache gives the same output as formatting from scratch.
+pub fn collect_tests() -> Vec<Trial> {
+    let dir = fixtures_dir().join("packages");
+    let mut paths = fs::read_dir(&dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension() == Some("typ".as_ref()))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .flat_map(|path| {
+            configs().into_iter().map(move |(config_name, config)| {
+                let path = path.clone();
+                let name = path.file_name().unwrap().to_string_lossy().into_owned();
+                Trial::test(format!("packages/{name} - {config_name}"), move || {
+                    check_incremental(&path, config_name, config)
+                })
+                .with_kind("incremental")
+            })
+        })
+        .collect()
+}
+
+fn check_incremental(path: &Path, config_name: &str, config: Config) -> Result<(), Failed> {
+    let mut source = read_source(path)?;
+    let t = Typstyle::new(config);
+    let mut cache = FormatCache::default();
+    let name = path.file_name().unwrap().as_encoded_bytes();
+    let seed = (name.iter().chain(config_name.as_bytes())).fold(0, |hash: u64, &b| {
+        hash.wrapping_mul(31).wrapping_add(b as u64)
+    });
+    let mut rng = Rng(seed | 1);
+
+    t.format_source_cached(source.clone(), &mut cache)
+        .map_err(|err| format!("failed to format {}: {err}", path.display()))?;
+    for i in 0..NUM_EDITS {
+        let text = source.text();
+        let mut start = floor_char_boundary(text, rng.below(text.len() + 1));
+        if rng.below(2) == 0 {
+            // Items are often inserted on lines of their own.
+            start = text[..start].rfind('\n').map_or(0, |i| i + 1);
+        }
+        let (range, replacement) = if rng.below(3) == 0 {
+            let end = floor_char_boundary(text, (start + rng.below(16)).min(text.len()));
+            (start..end, "")
+        } else {
+            (start..start, SNIPPETS[rng.below(SNIPPETS.len())])
+        };
+        let replaced = text[range.clone()].to_string();
+
+        let ok = check_edit(&t, &mut source, range.clone(), replacement, &mut cache)
+            .map_err(|err| format!("edit #{i} of {}: {err}", path.display()))?;
+        if !ok {
+            // Undo edits leading to syntax errors, so that later edits are formatted.
+            let range = range.start..range.start + replacement.len();
+            check_edit(&t, &mut source, range, &replaced, &mut cache)
+                .map_err(|err| format!("undo of edit #{i} of {}: {err}", path.display()))?;
+        }
+    }
+
+    // Setting how enums are numbered changes the layout of items cached before.
+    let end = source.text().len();
+    check_edit(
+        &t,
+        &mut source,
+        end..end,
+        "\n#[\n  1. a\n  2. b\n]\n",
+        &mut cache,
+    )
+    .map_err(|err| format!("enum edit of {}: {err}", path.display()))?;
+    check_edit(&t, &mut source, 0..0, "#set enum(start: 3)\n", &mut cache)
+        .map_err(|err| format!("enum set rule edit of {}: {err}", path.display()))?;
+    Ok(())
+}
+
+/// Applies the edit and checks the output against formatting from scratch.
+/// Returns whether formatting succeeded.
+fn check_edit(
+    t: &Typstyle,
+    source: &mut Source,
+    range: Range<usize>,
+    replacement: &str,
+    cache: &mut FormatCache,
+) -> Result<bool, String> {
+    let incremental = t
+        .format_source_incremental(source, range.clone(), replacement, cache)
+        .map_err(|err| err.to_string());
+    let expected = t
+        .format_text(source.text())
+        .render()
+        .map_err(|err| err.to_string());
+    if incremental != expected {
+        return Err(format!(
+            "replacing {range:?} with {replacement:?} gives a different output:\n{}",
+            similar_asserts::SimpleDiff::from_str(
+                expected.as_deref().unwrap_or_else(|err| err),
+                incremental.as_deref().unwrap_or_else(|err| err),
+                "expected",
+                "incremental",
+            )
+        ));
+    }
+    Ok(expected.is_ok())
+}
+
+/// A tiny deterministic random number generator (xorshift), so that failures can be reproduced.
+struct Rng(u64);
+
+impl Rng {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % n as u64) as usize
+    }
+}
+
+/// Moves the position back to a character boundary.
+fn floor_char_boundary(text: &str, mut pos: usize) -> usize {
+    while !text.is_char_boundary(pos) {
+        pos -= 1;
+    }
+    pos
+}
@@ -3,6 +3,7 @@
 mod repo_e2e;
 
 mod common;
+mod incremental;
 mod partial;
 mod unit;
 
@@ -14,6 +15,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     #[allow(unused_mut)]
     let mut tests = unit::collect_tests()?;
     tests.append(&mut partial::collect_tests());
+    tests.append(&mut incremental::collect_tests());
     #[cfg(feature = "consistency")]
     tests.append(&mut repo_e2e::collect_tests());
 
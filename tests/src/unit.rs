@@ -110,12 +110,14 @@ fn check_snapshot(path: &Path, width: usize) -> Result<(), Failed> {
     settings.set_omit_expression(true);
     settings.set_snapshot_path(path.parent().unwrap().join("snap"));
     settings.set_input_file(path);
+    // Erroneous sources are only formatted if requested.
+    let skipped = source.root().erroneous() && !cfg.format_erroneous;
     if source.root().erroneous() {
         settings.set_raw_info(&Content::Map(vec![("erroneous".into(), true.into())]));
     }
     settings.bind(|| {
         let snap_name = format!("{}-{width}", path.file_name().unwrap().to_str().unwrap());
-        if source.root().erroneous() {
+        if skipped {
             insta::assert_snapshot!(snap_name, "");
         } else {
             cfg.max_width = width;
@@ -136,7 +138,7 @@ fn check_snapshot(path: &Path, width: usize) -> Result<(), Failed> {
 fn check_convergence(path: &Path, width: usize) -> Result<(), Failed> {
     let (source, opt) = read_source_with_options(path)?;
     let mut cfg = opt.config;
-    if source.root().erroneous() {
+    if source.root().erroneous() && !cfg.format_erroneous {
         return Ok(());
     }
 
@@ -145,7 +147,7 @@ fn check_convergence(path: &Path, width: usize) -> Result<(), Failed> {
     let mut first_pass = t.format_source(source).render()?;
     for i in 0..=opt.relax_convergence {
         let new_source = Source::detached(&first_pass);
-        if new_source.root().erroneous() {
+        if new_source.root().erroneous() && !t.config().format_erroneous {
             panic!(
                 "the source becomes erroneous after {} iterations:\n{:#?}",
                 i + 1,
@@ -183,6 +185,8 @@ fn check_output_consistency(path: &Path, width: usize) -> Result<(), Failed> {
     }
 
     cfg.max_width = width;
+    // Formatting code in raw blocks changes the rendered raw text by design.
+    cfg.format_code_in_raw_blocks = false;
     let t = Typstyle::new(cfg);
 
     let mut err_sink = ErrorSink::new(format!("consistency {}", path.display()));